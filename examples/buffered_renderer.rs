@@ -0,0 +1,23 @@
+use raylib_imgui::{RaylibGui, RenderMode};
+use raylib::prelude::*;
+
+fn main() {
+  let (mut rl, thread) = raylib::init()
+    .size(800, 600)
+    .title("Buffered renderer")
+    .build();
+
+  let mut gui =
+    RaylibGui::with_render_mode(&mut rl, &thread, RenderMode::Buffered);
+  let mut open = true;
+
+  while !rl.window_should_close() {
+    let ui = gui.begin(&mut rl);
+    ui.show_demo_window(&mut open);
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+
+    gui.end();
+  }
+}
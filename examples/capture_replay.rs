@@ -0,0 +1,47 @@
+use raylib_imgui::{FrameCapture, RaylibGui, Renderer};
+use raylib::prelude::*;
+
+// F12 saves the current frame to `frame.rlig`, running with a path replays
+// a saved frame instead
+fn main() {
+  let replay = std::env::args().nth(1);
+
+  let (mut rl, thread) = raylib::init()
+    .size(800, 600)
+    .title("Capture and replay")
+    .build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+
+  if let Some(path) = replay {
+    let mut frame = FrameCapture::load(path).expect("invalid frame capture");
+    let atlas = frame.font_atlas.clone().expect("capture has no font atlas");
+    let texture =
+      rl.load_texture_from_image(&thread, &atlas.to_image()).unwrap();
+    frame.remap_texture(atlas.id.into(), (texture.id as usize).into());
+
+    while !rl.window_should_close() {
+      let mut d = rl.begin_drawing(&thread);
+      d.clear_background(Color::WHITE);
+      Renderer::render_capture(&frame);
+    }
+    return;
+  }
+
+  let mut open = true;
+  while !rl.window_should_close() {
+    let save = rl.is_key_pressed(KeyboardKey::KEY_F12);
+    let ui = gui.begin(&mut rl);
+    ui.show_demo_window(&mut open);
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+
+    if save {
+      let frame = gui.end_captured();
+      frame.save("frame.rlig").expect("failed to save frame.rlig");
+    } else {
+      gui.end();
+    }
+  }
+}
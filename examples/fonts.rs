@@ -0,0 +1,40 @@
+use imgui::FontGlyphRanges;
+use raylib::prelude::*;
+use raylib_imgui::{FontStack, RaylibGui};
+
+// cargo run --example fonts -- path/to/font.ttf
+fn main() {
+  let path = std::env::args().nth(1).expect("pass a .ttf or .otf file");
+
+  let (mut rl, thread) = raylib::init().size(800, 600).title("Fonts").build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+  let body = gui.add_font_from_file(&path, 18.0).unwrap();
+  let large = gui
+    .add_font(
+      FontStack::from_file(&path, 32.0)
+        .oversample(3, 2)
+        .glyph_ranges(FontGlyphRanges::cyrillic()),
+    )
+    .unwrap();
+  // edit the font file while this runs
+  gui.watch_fonts(true);
+  gui.set_error_hook(|error| eprintln!("{error}"));
+
+  while !rl.window_should_close() {
+    let [body, large] = [gui.font(body), gui.font(large)];
+    let ui = gui.begin(&mut rl);
+    ui.window("Fonts").build(|| {
+      ui.text("default font");
+      let _body = ui.push_font(body);
+      ui.text("body font at 18px");
+      let _large = ui.push_font(large);
+      ui.text("large font, Привет");
+    });
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+
+    gui.end();
+  }
+}
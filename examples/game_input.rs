@@ -0,0 +1,48 @@
+use raylib::prelude::*;
+use raylib_imgui::{Passthrough, RaylibGui};
+
+fn main() {
+  let (mut rl, thread) =
+    raylib::init().size(800, 600).title("Game input").build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+  gui.set_passthrough("Overlay", Passthrough::Mouse);
+
+  let mut player = Vector2::new(400.0, 300.0);
+  let mut radius = 20.0;
+  let mut notes = String::new();
+
+  while !rl.window_should_close() {
+    // game input first, imgui's capture flags are from the last frame
+    let input = gui.input(&rl);
+    let speed = 200.0 * rl.get_frame_time();
+    if input.is_key_down(KeyboardKey::KEY_RIGHT) {
+      player.x += speed;
+    }
+    if input.is_key_down(KeyboardKey::KEY_LEFT) {
+      player.x -= speed;
+    }
+    if input.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+      player = rl.get_mouse_position();
+    }
+    radius = (radius + input.get_mouse_wheel_move() * 2.0).max(5.0);
+
+    let ui = gui.begin(&mut rl);
+    ui.window("Notes").build(|| {
+      ui.text("typing here doesn't move the circle");
+      ui.input_text_multiline("##notes", &mut notes, [300.0, 100.0]).build();
+    });
+    // clicks on this window still reach the game
+    ui.window("Overlay")
+      .position([500.0, 450.0], imgui::Condition::FirstUseEver)
+      .build(|| {
+        ui.text(format!("{:.0}, {:.0}", player.x, player.y));
+      });
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+    d.draw_circle_v(player, radius, Color::MAROON);
+
+    gui.end();
+  }
+}
@@ -0,0 +1,53 @@
+use imgui::ConfigFlags;
+use raylib::prelude::*;
+use raylib_imgui::{GamepadLayout, GamepadSelect, RaylibGui};
+
+fn main() {
+  let (mut rl, thread) = raylib::init().size(800, 600).title("Gamepad").build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+  gui.context.io_mut().config_flags |= ConfigFlags::NAV_ENABLE_GAMEPAD;
+
+  let layouts = [
+    ("Xbox", GamepadLayout::Xbox),
+    ("PlayStation", GamepadLayout::PlayStation),
+    ("Nintendo", GamepadLayout::Nintendo),
+  ];
+  let mut layout = 0;
+  let mut pad = -1;
+  let mut dead_zone = gui.gamepad.dead_zone(GamepadAxis::GAMEPAD_AXIS_LEFT_X);
+  let mut open = true;
+
+  while !rl.window_should_close() {
+    let active = gui.gamepad.active();
+    let ui = gui.begin(&mut rl);
+    ui.show_demo_window(&mut open);
+
+    let mut changed = false;
+    ui.window("Gamepad").build(|| {
+      match active {
+        Some(active) => ui.text(format!("following pad {active}")),
+        None => ui.text("no pad connected"),
+      }
+      changed |=
+        ui.combo("layout", &mut layout, &layouts, |(name, _)| (*name).into());
+      changed |= ui.slider("pad, -1 for last used", -1, 3, &mut pad);
+      changed |= ui.slider("dead zone", 0.0, 0.9, &mut dead_zone);
+    });
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+
+    gui.end();
+
+    if changed {
+      gui.gamepad.set_layout(layouts[layout].1);
+      gui.gamepad.select(if pad < 0 {
+        GamepadSelect::LastActive
+      } else {
+        GamepadSelect::Index(pad)
+      });
+      gui.gamepad.set_dead_zones(dead_zone);
+    }
+  }
+}
@@ -0,0 +1,38 @@
+use raylib::prelude::*;
+use raylib_imgui::{IconFont, IconTable, RaylibGui};
+
+// cargo run --example icons -- MaterialIcons-Regular.ttf codepoints
+fn main() {
+  let mut args = std::env::args().skip(1);
+  let font = args.next().expect("pass the icon font");
+  let codepoints = args.next().expect("pass its codepoints file");
+
+  let (mut rl, thread) = raylib::init().size(800, 600).title("Icons").build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+  let default_font = gui.default_font();
+  gui
+    .merge_icons(
+      default_font,
+      &IconFont::material(&font).glyph_offset([0.0, 2.0]),
+    )
+    .unwrap();
+  let icons = IconTable::load(&codepoints).unwrap();
+
+  while !rl.window_should_close() {
+    let ui = gui.begin(&mut rl);
+    ui.window("Icons").build(|| {
+      ui.button(icons.label("save", "Save"));
+      ui.same_line();
+      ui.button(icons.label("delete", "Delete"));
+      if let Some(_menu) = ui.begin_menu(icons.label("settings", "Settings")) {
+        ui.menu_item(icons.label("palette", "Theme"));
+      }
+    });
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+
+    gui.end();
+  }
+}
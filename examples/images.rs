@@ -0,0 +1,46 @@
+use raylib_imgui::{RaylibGui, RaylibUiExt};
+use raylib::prelude::*;
+
+fn main() {
+  let (mut rl, thread) = raylib::init()
+    .size(800, 600)
+    .title("Images")
+    .build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+
+  let image = Image::gen_image_checked(64, 64, 8, 8, Color::RED, Color::WHITE);
+  let texture = rl.load_texture_from_image(&thread, &image).unwrap();
+  let mut scene = rl.load_render_texture(&thread, 320, 180).unwrap();
+  gui.textures.insert_ref(&texture);
+  gui.textures.insert_ref(scene.texture());
+
+  while !rl.window_should_close() {
+    {
+      let mut d = rl.begin_texture_mode(&thread, &mut scene);
+      d.clear_background(Color::SKYBLUE);
+      d.draw_circle(160, 90, 40.0, Color::ORANGE);
+    }
+
+    let ui = gui.begin(&mut rl);
+    ui.window("Texture").build(|| {
+      ui.image(&texture);
+      ui.image_rect(
+        &texture,
+        [64.0, 64.0],
+        Rectangle::new(0.0, 0.0, -32.0, 32.0),
+      );
+      if ui.image_button("checker", &texture, [32.0, 32.0]) {
+        println!("clicked");
+      }
+    });
+    ui.window("Scene").build(|| {
+      ui.image_render_texture_fit(&scene, true);
+    });
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+
+    gui.end();
+  }
+}
@@ -0,0 +1,40 @@
+use imgui::Condition;
+use raylib_imgui::RaylibGui;
+use raylib::prelude::*;
+
+// draws well over 64k vertices into a single window so imgui has to split
+// the draw list with vertex offsets
+fn main() {
+  let (mut rl, thread) = raylib::init()
+    .size(800, 600)
+    .title("Large mesh")
+    .build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+
+  while !rl.window_should_close() {
+    let ui = gui.begin(&mut rl);
+    ui.window("Plot")
+      .size([600.0, 400.0], Condition::FirstUseEver)
+      .build(|| {
+        let draw_list = ui.get_window_draw_list();
+        let [x, y] = ui.cursor_screen_pos();
+        let [w, h] = ui.content_region_avail();
+        for i in 0..40_000 {
+          let t = i as f32 / 40_000.0;
+          let px = x + t * w;
+          let py = y + h * 0.5 + (t * 200.0).sin() * h * 0.4;
+          draw_list
+            .add_rect([px, py], [px + 1.0, py + 1.0], [0.2, 0.8, 0.3, 1.0])
+            .filled(true)
+            .build();
+        }
+        ui.text(format!("vertices: {}", ui.io().metrics_render_vertices));
+      });
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+
+    gui.end();
+  }
+}
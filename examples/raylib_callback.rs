@@ -0,0 +1,33 @@
+use raylib_imgui::{RaylibGui, RaylibUiExt};
+use raylib::prelude::*;
+
+fn main() {
+  let (mut rl, thread) = raylib::init()
+    .size(800, 600)
+    .title("Raylib callbacks")
+    .build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+
+  while !rl.window_should_close() {
+    let time = rl.get_time() as f32;
+    let ui = gui.begin(&mut rl);
+    ui.window("Canvas").build(|| {
+      ui.raylib_callback(move |ctx| {
+        let size = ctx.size();
+        let mut d = ctx.begin_local();
+        d.draw_rectangle_v(Vector2::zero(), size, Color::DARKBLUE);
+        d.draw_circle_v(
+          Vector2::new(size.x * 0.5, size.y * 0.5),
+          20.0 + time.sin() * 10.0,
+          Color::YELLOW,
+        );
+      });
+    });
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+
+    gui.end();
+  }
+}
@@ -0,0 +1,26 @@
+use raylib::prelude::*;
+use raylib_imgui::RaylibGui;
+
+fn main() {
+  let (mut rl, thread) =
+    raylib::init().size(800, 600).title("Raylib font").build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+  let pixel_font = gui.add_raylib_font(&rl.get_font_default()).unwrap();
+
+  while !rl.window_should_close() {
+    let pixel_font = gui.font(pixel_font);
+    let ui = gui.begin(&mut rl);
+    ui.window("Raylib font").build(|| {
+      let _font = ui.push_font(pixel_font);
+      ui.text("drawn with raylib's default font");
+      ui.button("Button");
+    });
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+    d.draw_text("drawn by raylib", 10, 570, 20, Color::DARKGRAY);
+
+    gui.end();
+  }
+}
@@ -0,0 +1,35 @@
+use raylib_imgui::RaylibGui;
+use raylib::prelude::*;
+
+fn main() {
+  let (mut rl, thread) = raylib::init()
+    .size(800, 600)
+    .title("Render to texture")
+    .build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+  let mut target = rl.load_render_texture(&thread, 800, 600).unwrap();
+  let mut open = true;
+
+  while !rl.window_should_close() {
+    let ui = gui.begin(&mut rl);
+    ui.show_demo_window(&mut open);
+
+    {
+      let mut d = rl.begin_texture_mode(&thread, &mut target);
+      d.clear_background(Color::BLANK);
+    }
+    gui.render_to(&mut target);
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::DARKGRAY);
+    d.draw_texture_pro(
+      &target,
+      Rectangle::new(0.0, 0.0, 800.0, -600.0),
+      Rectangle::new(400.0, 300.0, 800.0, 600.0),
+      Vector2::new(400.0, 300.0),
+      (d.get_time() as f32).sin() * 5.0,
+      Color::WHITE,
+    );
+  }
+}
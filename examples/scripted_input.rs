@@ -0,0 +1,50 @@
+use raylib::prelude::*;
+use raylib_imgui::{InputFrame, RaylibGui, ScriptedInput, SoftwareRenderer};
+
+// runs without a window, types into a text field and writes the result
+// to scripted_input.png
+fn main() {
+  // click into the text field, type into it and leave the mouse over it
+  let field = Some(Vector2::new(150.0, 58.0));
+  let mut frames = vec![
+    InputFrame { mouse_position: field, ..InputFrame::default() },
+    InputFrame {
+      mouse_position: field,
+      mouse_down: vec![MouseButton::MOUSE_BUTTON_LEFT],
+      ..InputFrame::default()
+    },
+    InputFrame { mouse_position: field, ..InputFrame::default() },
+  ];
+  for c in "typed by a script".chars() {
+    frames.push(InputFrame {
+      mouse_position: field,
+      chars: vec![c],
+      ..InputFrame::default()
+    });
+  }
+  let mut input = ScriptedInput::new(frames);
+
+  let mut gui = RaylibGui::headless(&input);
+  let renderer = SoftwareRenderer::new(&mut gui.context);
+  let mut text = String::new();
+
+  // once the script ran out its last frame is drawn to an image
+  loop {
+    let more = input.advance();
+    let ui = gui.begin(&mut input);
+    ui.window("Script")
+      .position([20.0, 20.0], imgui::Condition::Always)
+      .size([300.0, 100.0], imgui::Condition::Always)
+      .build(|| {
+        ui.input_text("##text", &mut text).build();
+      });
+    if more {
+      gui.end();
+    } else {
+      let image = renderer.render(gui.context.render());
+      image.export_png("scripted_input.png");
+      break;
+    }
+  }
+  println!("{text}");
+}
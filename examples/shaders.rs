@@ -0,0 +1,53 @@
+use imgui::Image as ImImage;
+use raylib_imgui::{RaylibGui, TextureOverride, UiBlend};
+use raylib::prelude::*;
+
+const GRAYSCALE: &str = r#"
+#version 330
+in vec2 fragTexCoord;
+in vec4 fragColor;
+uniform sampler2D texture0;
+uniform vec4 colDiffuse;
+out vec4 finalColor;
+
+void main() {
+  vec4 texel = texture(texture0, fragTexCoord) * fragColor * colDiffuse;
+  float gray = dot(texel.rgb, vec3(0.299, 0.587, 0.114));
+  finalColor = vec4(vec3(gray), texel.a);
+}
+"#;
+
+fn main() {
+  let (mut rl, thread) = raylib::init()
+    .size(800, 600)
+    .title("Shaders")
+    .build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+  let grayscale = rl.load_shader_from_memory(&thread, None, Some(GRAYSCALE));
+
+  let image =
+    Image::gen_image_gradient_radial(64, 64, 0.0, Color::RED, Color::BLUE);
+  let texture = rl.load_texture_from_image(&thread, &image).unwrap();
+  let handle = gui.textures.insert_ref(&texture);
+  let disabled =
+    gui.texture_variant(handle, TextureOverride::new().shader(&grayscale));
+
+  let mut additive = false;
+
+  while !rl.window_should_close() {
+    let ui = gui.begin(&mut rl);
+    ui.window("Shaders").build(|| {
+      ui.checkbox("additive", &mut additive);
+      ImImage::new(handle.id(), [64.0, 64.0]).build(ui);
+      ui.same_line();
+      ImImage::new(disabled, [64.0, 64.0]).build(ui);
+    });
+    gui.set_blend(if additive { UiBlend::Additive } else { UiBlend::Alpha });
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::DARKGRAY);
+
+    gui.end();
+  }
+}
@@ -0,0 +1,19 @@
+use imgui::Context;
+use raylib_imgui::SoftwareRenderer;
+
+// renders a frame without opening a window and writes it to a png
+fn main() {
+  let mut ctx = Context::create();
+  ctx.set_ini_filename(None);
+  ctx.io_mut().display_size = [640.0, 480.0];
+  ctx.io_mut().delta_time = 1.0 / 60.0;
+
+  let renderer = SoftwareRenderer::new(&mut ctx);
+
+  let ui = ctx.new_frame();
+  let mut open = true;
+  ui.show_demo_window(&mut open);
+
+  let image = renderer.render(ctx.render());
+  image.export_png("software_render.png");
+}
@@ -0,0 +1,29 @@
+use raylib::prelude::*;
+use raylib_imgui::RaylibGui;
+
+fn main() {
+  let (mut rl, thread) =
+    raylib::init().size(800, 600).title("Stats overlay").build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+  gui.show_stats_overlay(true);
+  let mut open = true;
+
+  while !rl.window_should_close() {
+    let stats = gui.stats();
+    let ui = gui.begin(&mut rl);
+    ui.show_demo_window(&mut open);
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+    d.draw_text(
+      &format!("{} batch flushes last frame", stats.batch_flushes),
+      10,
+      570,
+      20,
+      Color::DARKGRAY,
+    );
+
+    gui.end();
+  }
+}
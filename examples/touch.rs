@@ -0,0 +1,23 @@
+use raylib::prelude::*;
+use raylib_imgui::RaylibGui;
+
+fn main() {
+  let (mut rl, thread) = raylib::init().size(800, 600).title("Touch").build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+  gui.touch.set_enabled(true);
+  gui.touch.set_pinch_to_scale(true);
+  let mut open = true;
+
+  while !rl.window_should_close() {
+    let ui = gui.begin(&mut rl);
+    // tap to click, hold to right-click, drag two fingers to scroll and
+    // pinch to zoom
+    ui.show_demo_window(&mut open);
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+
+    gui.end();
+  }
+}
@@ -0,0 +1,45 @@
+use raylib::prelude::*;
+use raylib_imgui::RaylibGui;
+
+fn main() {
+  let (mut rl, thread) = raylib::init()
+    .size(800, 600)
+    .title("Ui scale")
+    .resizable()
+    .highdpi()
+    .build();
+
+  let mut gui = RaylibGui::new(&mut rl, &thread);
+  gui.set_auto_ui_scale(true);
+  let mut auto = true;
+  let mut scale = gui.ui_scale();
+  let mut open = true;
+
+  while !rl.window_should_close() {
+    let ui = gui.begin(&mut rl);
+    ui.show_demo_window(&mut open);
+
+    let mut changed = false;
+    ui.window("Scale").build(|| {
+      changed |= ui.checkbox("follow monitor dpi", &mut auto);
+      changed |= ui.slider("scale", 0.5, 3.0, &mut scale);
+    });
+
+    let mut d = rl.begin_drawing(&thread);
+    d.clear_background(Color::WHITE);
+
+    gui.end();
+
+    // the atlas can only be rebuilt between frames
+    if changed {
+      if auto {
+        gui.set_auto_ui_scale(true);
+      } else {
+        gui.set_ui_scale(scale);
+      }
+    }
+    if auto {
+      scale = gui.ui_scale();
+    }
+  }
+}
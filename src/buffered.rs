@@ -0,0 +1,460 @@
+use crate::materials::{default_shader, Materials, UiBlend};
+use crate::{vertex_index, FrameStats, RenderState, RenderTarget};
+use imgui::{
+  internal::RawWrapper, Context, DrawCmd, DrawCmdParams, DrawData, DrawIdx,
+  DrawVert, TextureId,
+};
+use raylib::ffi;
+use raylib::prelude::*;
+use std::ffi::c_void;
+use std::iter::Peekable;
+use std::mem::{offset_of, size_of};
+
+// attribute locations used by rlgl's default shader
+const ATTRIB_POSITION: u32 = 0;
+const ATTRIB_TEXCOORD: u32 = 1;
+const ATTRIB_COLOR: u32 = 3;
+
+// gpu buffers holding one imgui draw list, reused across frames and only
+// reallocated when the list outgrows them
+#[derive(Default)]
+struct DrawListBuffers {
+  vao: u32,
+  vbo: u32,
+  ebo: u32,
+  vtx_capacity: usize,
+  idx_capacity: usize,
+}
+
+impl DrawListBuffers {
+  unsafe fn upload(&mut self, vtx_buffer: &[DrawVert], idx_buffer: &[DrawIdx]) {
+    if self.vao == 0 {
+      self.vao = ffi::rlLoadVertexArray();
+    }
+    ffi::rlEnableVertexArray(self.vao);
+
+    let vtx_size = (vtx_buffer.len() * size_of::<DrawVert>()) as i32;
+    if vtx_buffer.len() > self.vtx_capacity {
+      if self.vbo != 0 {
+        ffi::rlUnloadVertexBuffer(self.vbo);
+      }
+      self.vtx_capacity = vtx_buffer.len().next_power_of_two();
+      self.vbo = ffi::rlLoadVertexBuffer(
+        std::ptr::null(),
+        (self.vtx_capacity * size_of::<DrawVert>()) as i32,
+        true,
+      );
+    }
+    ffi::rlUpdateVertexBuffer(
+      self.vbo,
+      vtx_buffer.as_ptr() as *const c_void,
+      vtx_size,
+      0,
+    );
+
+    let idx_size = (idx_buffer.len() * size_of::<DrawIdx>()) as i32;
+    if idx_buffer.len() > self.idx_capacity {
+      if self.ebo != 0 {
+        ffi::rlUnloadVertexBuffer(self.ebo);
+      }
+      self.idx_capacity = idx_buffer.len().next_power_of_two();
+      self.ebo = ffi::rlLoadVertexBufferElement(
+        std::ptr::null(),
+        (self.idx_capacity * size_of::<DrawIdx>()) as i32,
+        true,
+      );
+    }
+    ffi::rlUpdateVertexBufferElements(
+      self.ebo,
+      idx_buffer.as_ptr() as *const c_void,
+      idx_size,
+      0,
+    );
+  }
+
+  // rlgl has no base vertex draw call, so the vertex offset is applied by
+  // pointing the attributes further into the vertex buffer
+  unsafe fn bind(&self, vtx_offset: usize) {
+    let stride = size_of::<DrawVert>() as i32;
+    let base = attribute_base(vtx_offset);
+
+    ffi::rlEnableVertexArray(self.vao);
+    ffi::rlEnableVertexBuffer(self.vbo);
+    ffi::rlSetVertexAttribute(
+      ATTRIB_POSITION,
+      2,
+      ffi::RL_FLOAT as i32,
+      false,
+      stride,
+      base + offset_of!(DrawVert, pos) as i32,
+    );
+    ffi::rlEnableVertexAttribute(ATTRIB_POSITION);
+    ffi::rlSetVertexAttribute(
+      ATTRIB_TEXCOORD,
+      2,
+      ffi::RL_FLOAT as i32,
+      false,
+      stride,
+      base + offset_of!(DrawVert, uv) as i32,
+    );
+    ffi::rlEnableVertexAttribute(ATTRIB_TEXCOORD);
+    ffi::rlSetVertexAttribute(
+      ATTRIB_COLOR,
+      4,
+      ffi::RL_UNSIGNED_BYTE as i32,
+      true,
+      stride,
+      base + offset_of!(DrawVert, col) as i32,
+    );
+    ffi::rlEnableVertexAttribute(ATTRIB_COLOR);
+    ffi::rlEnableVertexBufferElement(self.ebo);
+  }
+
+  unsafe fn unload(&mut self) {
+    if self.vao != 0 {
+      ffi::rlUnloadVertexArray(self.vao);
+    }
+    if self.vbo != 0 {
+      ffi::rlUnloadVertexBuffer(self.vbo);
+    }
+    if self.ebo != 0 {
+      ffi::rlUnloadVertexBuffer(self.ebo);
+    }
+    *self = DrawListBuffers::default();
+  }
+}
+
+// where the attributes start for a command's vertices, the gpu then reads
+// index `i` from the vertex at `vertex_index(vtx_offset, i)`
+fn attribute_base(vtx_offset: usize) -> i32 {
+  (vertex_index(vtx_offset, 0) * size_of::<DrawVert>()) as i32
+}
+
+// uploads every draw list into rlgl vertex arrays once per frame and issues
+// one indexed draw per command instead of pushing vertices through the batch
+#[derive(Default)]
+pub struct BufferedRenderer {
+  buffers: Vec<DrawListBuffers>,
+}
+
+impl BufferedRenderer {
+  pub fn new() -> BufferedRenderer {
+    BufferedRenderer::default()
+  }
+
+  pub fn render(&mut self, ctx: &mut Context) {
+    let target = RenderTarget::screen(ctx.io());
+    let materials = Materials::default();
+    let mut stats = FrameStats::default();
+    self.render_draw_data(ctx.render(), target, None, &materials, &mut stats);
+  }
+
+  pub(crate) fn render_draw_data(
+    &mut self,
+    draw_data: &DrawData,
+    target: RenderTarget,
+    materials: &Materials,
+    stats: &mut FrameStats,
+  ) {
+    unsafe {
+      ffi::rlDrawRenderBatchActive();
+      ffi::rlDisableBackfaceCulling();
+      let state = RenderState::capture(materials);
+      let mvp: ffi::Matrix =
+        (Matrix::from(state.modelview) * Matrix::from(state.projection)).into();
+
+      let count = draw_data.draw_lists_count();
+      if self.buffers.len() < count {
+        self.buffers.resize_with(count, DrawListBuffers::default);
+      }
+
+      let mut pass = Pass::begin(mvp, target, materials, stats);
+      let lists = draw_data.draw_lists().zip(&mut self.buffers);
+      for (draw_list, buffers) in lists {
+        buffers.upload(draw_list.vtx_buffer(), draw_list.idx_buffer());
+        pass.vtx_offset = None;
+
+        pass.stats.commands += draw_list.commands().count();
+        for cmd in merged(draw_list.commands()) {
+          match cmd {
+            DrawCmd::Elements { count, cmd_params } => {
+              pass.draw(buffers, count, cmd_params)
+            },
+            DrawCmd::ResetRenderState => {
+              end_pass();
+              state.reset();
+              pass.restart();
+            },
+            DrawCmd::RawCallback { callback, raw_cmd } => {
+              end_pass();
+              let clip_rect = &(*raw_cmd).ClipRect;
+              target.scissor([
+                clip_rect.x,
+                clip_rect.y,
+                clip_rect.z,
+                clip_rect.w,
+              ]);
+              callback(draw_list.raw(), raw_cmd);
+              state.reset();
+              pass.restart();
+            },
+          }
+        }
+      }
+      end_pass();
+      state.finish();
+    }
+  }
+}
+
+// consecutive commands that only continue the previous one's index range
+// are merged into a single draw call
+fn merged<I: Iterator<Item = DrawCmd>>(commands: I) -> Merged<I> {
+  Merged { commands: commands.peekable() }
+}
+
+struct Merged<I: Iterator<Item = DrawCmd>> {
+  commands: Peekable<I>,
+}
+
+impl<I: Iterator<Item = DrawCmd>> Iterator for Merged<I> {
+  type Item = DrawCmd;
+
+  fn next(&mut self) -> Option<DrawCmd> {
+    let cmd = self.commands.next()?;
+    let DrawCmd::Elements { mut count, cmd_params } = cmd else {
+      return Some(cmd);
+    };
+    while let Some(&DrawCmd::Elements { count: next_count, cmd_params: next }) =
+      self.commands.peek()
+    {
+      if !continues(&cmd_params, count, &next) {
+        break;
+      }
+      count += next_count;
+      self.commands.next();
+    }
+    Some(DrawCmd::Elements { count, cmd_params })
+  }
+}
+
+fn continues(
+  previous: &DrawCmdParams,
+  previous_count: usize,
+  next: &DrawCmdParams,
+) -> bool {
+  previous.clip_rect == next.clip_rect
+    && previous.texture_id == next.texture_id
+    && previous.vtx_offset == next.vtx_offset
+    && previous.idx_offset + previous_count == next.idx_offset
+}
+
+// gl state of the running pass, tracked so that state which didn't change
+// between two draws isn't issued again
+struct Pass<'a> {
+  mvp: ffi::Matrix,
+  target: RenderTarget,
+  materials: &'a Materials,
+  stats: &'a mut FrameStats,
+  fallback: ffi::Shader,
+  shader: ffi::Shader,
+  blend: UiBlend,
+  clip_rect: Option<[f32; 4]>,
+  texture: Option<TextureId>,
+  vtx_offset: Option<usize>,
+}
+
+impl<'a> Pass<'a> {
+  unsafe fn begin(
+    mvp: ffi::Matrix,
+    target: RenderTarget,
+    materials: &'a Materials,
+    stats: &'a mut FrameStats,
+  ) -> Pass<'a> {
+    let fallback = default_shader();
+    begin_pass(fallback, mvp);
+    Pass {
+      mvp,
+      target,
+      materials,
+      stats,
+      fallback,
+      shader: fallback,
+      blend: UiBlend::Alpha,
+      clip_rect: None,
+      texture: None,
+      vtx_offset: None,
+    }
+  }
+
+  // after the render state was reset
+  unsafe fn restart(&mut self) {
+    self.shader = self.fallback;
+    self.blend = UiBlend::Alpha;
+    self.clip_rect = None;
+    self.texture = None;
+    self.vtx_offset = None;
+    begin_pass(self.shader, self.mvp);
+  }
+
+  unsafe fn draw(
+    &mut self,
+    buffers: &DrawListBuffers,
+    count: usize,
+    params: DrawCmdParams,
+  ) {
+    let material = self.materials.resolve(params.texture_id);
+    let shader = material.shader.unwrap_or(self.fallback);
+    if material.blend != self.blend || shader.id != self.shader.id {
+      // changing the blend mode flushes rlgl's batch, which can leave
+      // another program bound
+      material.blend.apply();
+      self.shader = shader;
+      self.blend = material.blend;
+      self.texture = None;
+      self.vtx_offset = None;
+      begin_pass(self.shader, self.mvp);
+      self.stats.batch_flushes += 1;
+    }
+
+    if self.clip_rect != Some(params.clip_rect) {
+      self.target.scissor(params.clip_rect);
+      self.clip_rect = Some(params.clip_rect);
+      self.stats.scissor_changes += 1;
+    }
+
+    if self.texture != Some(material.texture) {
+      ffi::rlEnableTexture(material.texture.id() as _);
+      self.texture = Some(material.texture);
+      self.stats.texture_switches += 1;
+    }
+
+    if self.vtx_offset != Some(params.vtx_offset) {
+      buffers.bind(params.vtx_offset);
+      self.vtx_offset = Some(params.vtx_offset);
+    }
+
+    ffi::rlDrawVertexArrayElements(
+      params.idx_offset as i32,
+      count as i32,
+      std::ptr::null(),
+    );
+    self.stats.batch_flushes += 1;
+  }
+}
+
+impl Drop for BufferedRenderer {
+  fn drop(&mut self) {
+    unsafe {
+      for buffers in &mut self.buffers {
+        buffers.unload();
+      }
+    }
+  }
+}
+
+unsafe fn begin_pass(shader: ffi::Shader, mvp: ffi::Matrix) {
+  let locs = shader.locs;
+  let white = [1.0f32; 4];
+
+  ffi::rlEnableShader(shader.id);
+  ffi::rlSetUniformMatrix(
+    *locs.add(ShaderLocationIndex::SHADER_LOC_MATRIX_MVP as usize),
+    mvp,
+  );
+  ffi::rlSetUniform(
+    *locs.add(ShaderLocationIndex::SHADER_LOC_COLOR_DIFFUSE as usize),
+    white.as_ptr() as *const c_void,
+    ffi::rlShaderUniformDataType::RL_SHADER_UNIFORM_VEC4 as i32,
+    1,
+  );
+  ffi::rlActiveTextureSlot(0);
+}
+
+unsafe fn end_pass() {
+  ffi::rlDisableVertexArray();
+  ffi::rlDisableVertexBuffer();
+  ffi::rlDisableVertexBufferElement();
+  ffi::rlDisableTexture();
+  ffi::rlDisableShader();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn offsets_vertices_past_16_bit_indices() {
+    let stride = size_of::<DrawVert>();
+    assert_eq!(vertex_index(0, 65535), 65535);
+    assert_eq!(vertex_index(65536, 0), 65536);
+    assert_eq!(vertex_index(65536, 65535), 131071);
+
+    // the buffer offset plus the gpu's index stride lands on the same vertex
+    for (vtx_offset, index) in [(0, 65535), (65535, 1), (65536, 0)] {
+      let fetched = attribute_base(vtx_offset) as usize + index * stride;
+      assert_eq!(fetched, vertex_index(vtx_offset, index as u16) * stride);
+    }
+  }
+  fn elements(idx_offset: usize, clip_x: f32, texture: usize) -> DrawCmd {
+    DrawCmd::Elements {
+      count: 6,
+      cmd_params: DrawCmdParams {
+        clip_rect: [clip_x, 0.0, 100.0, 100.0],
+        texture_id: TextureId::new(texture),
+        vtx_offset: 0,
+        idx_offset,
+      },
+    }
+  }
+
+  // (count, idx_offset) of what is drawn, none for anything else
+  fn draws(commands: Vec<DrawCmd>) -> Vec<Option<(usize, usize)>> {
+    merged(commands.into_iter())
+      .map(|cmd| match cmd {
+        DrawCmd::Elements { count, cmd_params } => {
+          Some((count, cmd_params.idx_offset))
+        },
+        _ => None,
+      })
+      .collect()
+  }
+
+  unsafe extern "C" fn callback(
+    _: *const imgui::sys::ImDrawList,
+    _: *const imgui::sys::ImDrawCmd,
+  ) {
+  }
+
+  #[test]
+  fn merges_commands_continuing_the_previous_one() {
+    let commands = vec![elements(0, 0.0, 1), elements(6, 0.0, 1)];
+    assert_eq!(draws(commands), [Some((12, 0))]);
+    let commands =
+      vec![elements(0, 0.0, 1), elements(6, 0.0, 1), elements(12, 0.0, 1)];
+    assert_eq!(draws(commands), [Some((18, 0))]);
+  }
+
+  #[test]
+  fn keeps_commands_apart_that_change_state_or_skip_indices() {
+    let commands = vec![elements(0, 0.0, 1), elements(6, 10.0, 1)];
+    assert_eq!(draws(commands), [Some((6, 0)), Some((6, 6))]);
+    let commands = vec![elements(0, 0.0, 1), elements(6, 0.0, 2)];
+    assert_eq!(draws(commands), [Some((6, 0)), Some((6, 6))]);
+    let commands = vec![elements(0, 0.0, 1), elements(12, 0.0, 1)];
+    assert_eq!(draws(commands), [Some((6, 0)), Some((6, 12))]);
+
+    let raw_cmd = std::ptr::null();
+    let commands = vec![
+      elements(0, 0.0, 1),
+      DrawCmd::RawCallback { callback, raw_cmd },
+      elements(6, 0.0, 1),
+      DrawCmd::ResetRenderState,
+      elements(12, 0.0, 1),
+    ];
+    assert_eq!(
+      draws(commands),
+      [Some((6, 0)), None, Some((6, 6)), None, Some((6, 12))]
+    );
+  }
+}
@@ -0,0 +1,130 @@
+use imgui::{internal::RawWrapper, sys, Io, Ui};
+use raylib::ffi;
+use raylib::prelude::*;
+use std::cell::RefCell;
+use std::ffi::c_void;
+
+type CallbackFn = Box<dyn FnOnce(&mut RaylibCallbackContext)>;
+
+struct PendingCallback {
+  origin: Vector2,
+  size: Vector2,
+  // taken by the first render, a frame rendered again skips it
+  func: Option<CallbackFn>,
+}
+
+// the closures recorded this frame, owned by `RaylibGui` and dropped when
+// the next frame starts. draw commands only carry an index into them, so a
+// frame that is never rendered or rendered twice can't leak or free twice
+#[derive(Default)]
+pub(crate) struct Callbacks {
+  pending: RefCell<Vec<PendingCallback>>,
+}
+
+impl Callbacks {
+  // lets `add_callback` and `run_callback` find the closures through the
+  // current context, `self` must not move while the context lives
+  pub(crate) fn install(&self, io: &mut Io) {
+    unsafe {
+      io.raw_mut().UserData = self as *const Callbacks as *mut c_void;
+    }
+  }
+
+  pub(crate) fn clear(&self) {
+    self.pending.borrow_mut().clear();
+  }
+
+  unsafe fn current<'a>() -> Option<&'a Callbacks> {
+    ((*sys::igGetIO()).UserData as *const Callbacks).as_ref()
+  }
+}
+
+// handed to closures recorded with `RaylibUiExt::raylib_callback` while the
+// draw list is rendered, scissoring is already set to `clip_rect`
+pub struct RaylibCallbackContext {
+  clip_rect: Rectangle,
+  origin: Vector2,
+  size: Vector2,
+}
+
+impl RaylibCallbackContext {
+  pub fn clip_rect(&self) -> Rectangle {
+    self.clip_rect
+  }
+
+  // screen position of the cursor when the callback was recorded
+  pub fn origin(&self) -> Vector2 {
+    self.origin
+  }
+
+  // content region that was still available when the callback was recorded
+  pub fn size(&self) -> Vector2 {
+    self.size
+  }
+
+  pub fn to_screen(&self, local: Vector2) -> Vector2 {
+    self.origin + local
+  }
+
+  // 2d mode where (0, 0) is `origin`
+  pub fn begin_local(&mut self) -> RaylibMode2D<'_, Self> {
+    let camera = Camera2D {
+      offset: self.origin,
+      target: Vector2::zero(),
+      rotation: 0.0,
+      zoom: 1.0,
+    };
+    self.begin_mode2D(camera)
+  }
+}
+
+impl RaylibDraw for RaylibCallbackContext {}
+
+pub(crate) fn add_callback<F>(ui: &Ui, func: F)
+where
+  F: FnOnce(&mut RaylibCallbackContext) + 'static,
+{
+  let callbacks = unsafe { Callbacks::current() }
+    .expect("raylib_callback needs a context created by RaylibGui");
+  let [x, y] = ui.cursor_screen_pos();
+  let [w, h] = ui.content_region_avail();
+  let mut pending = callbacks.pending.borrow_mut();
+  let index = pending.len();
+  pending.push(PendingCallback {
+    origin: Vector2::new(x, y),
+    size: Vector2::new(w, h),
+    func: Some(Box::new(func)),
+  });
+
+  unsafe {
+    sys::ImDrawList_AddCallback(
+      sys::igGetWindowDrawList(),
+      Some(run_callback),
+      index as *mut c_void,
+    );
+  }
+}
+
+unsafe extern "C" fn run_callback(
+  _draw_list: *const sys::ImDrawList,
+  cmd: *const sys::ImDrawCmd,
+) {
+  let Some(callbacks) = Callbacks::current() else { return };
+  let index = (*cmd).UserCallbackData as usize;
+  // the borrow ends before the closure runs
+  let (origin, size, func) = {
+    let mut pending = callbacks.pending.borrow_mut();
+    let Some(pending) = pending.get_mut(index) else { return };
+    let Some(func) = pending.func.take() else { return };
+    (pending.origin, pending.size, func)
+  };
+  let clip = (*cmd).ClipRect;
+  let mut ctx = RaylibCallbackContext {
+    clip_rect: Rectangle::new(clip.x, clip.y, clip.z - clip.x, clip.w - clip.y),
+    origin,
+    size,
+  };
+
+  func(&mut ctx);
+  ffi::rlDrawRenderBatchActive();
+}
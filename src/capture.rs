@@ -0,0 +1,533 @@
+use crate::software;
+use imgui::{Context, DrawCmd, DrawCmdParams, DrawData, DrawVert, TextureId};
+use raylib::prelude::*;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"RLIG";
+const VERSION: u32 = 1;
+// larger than any texture a gpu takes, keeps a corrupt atlas size from
+// asking for gigabytes
+const MAX_TEXTURE_SIZE: usize = 16384;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CapturedParams {
+  pub clip_rect: [f32; 4],
+  pub texture_id: usize,
+  pub vtx_offset: usize,
+  pub idx_offset: usize,
+}
+
+impl From<CapturedParams> for DrawCmdParams {
+  fn from(params: CapturedParams) -> DrawCmdParams {
+    DrawCmdParams {
+      clip_rect: params.clip_rect,
+      texture_id: params.texture_id.into(),
+      vtx_offset: params.vtx_offset,
+      idx_offset: params.idx_offset,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CapturedCmd {
+  Elements { count: usize, params: CapturedParams },
+  ResetRenderState,
+  // only the position of a callback is kept, it can't be replayed
+  Callback,
+}
+
+#[derive(Clone, Default)]
+pub struct CapturedDrawList {
+  pub vtx_buffer: Vec<DrawVert>,
+  pub idx_buffer: Vec<u16>,
+  pub commands: Vec<CapturedCmd>,
+}
+
+impl CapturedDrawList {
+  // whether an elements command only reads indices and vertices that are
+  // in the buffers
+  pub(crate) fn in_range(&self, count: usize, params: &CapturedParams) -> bool {
+    let indices = params
+      .idx_offset
+      .checked_add(count)
+      .and_then(|end| self.idx_buffer.get(params.idx_offset..end));
+    let vertices = self.vtx_buffer.len().saturating_sub(params.vtx_offset);
+    indices.is_some_and(|indices| {
+      indices.iter().all(|&index| (index as usize) < vertices)
+    })
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapturedTexture {
+  pub id: usize,
+  pub width: usize,
+  pub height: usize,
+  // rgba8, rows top to bottom
+  pub data: Vec<u8>,
+}
+
+impl CapturedTexture {
+  pub(crate) fn font_atlas(ctx: &mut Context) -> CapturedTexture {
+    let fonts = ctx.fonts();
+    let id = fonts.tex_id.id();
+    let texture = fonts.build_rgba32_texture();
+    CapturedTexture {
+      id,
+      width: texture.width as usize,
+      height: texture.height as usize,
+      data: texture.data.to_vec(),
+    }
+  }
+
+  pub fn to_image(&self) -> Image {
+    software::image_from_rgba8(self.width, self.height, &self.data)
+  }
+}
+
+// a copy of one frame's draw data, see `RaylibGui::end_captured` and
+// `Renderer::render_capture`
+#[derive(Clone, Default)]
+pub struct FrameCapture {
+  pub display_pos: [f32; 2],
+  pub display_size: [f32; 2],
+  pub framebuffer_scale: [f32; 2],
+  pub draw_lists: Vec<CapturedDrawList>,
+  pub font_atlas: Option<CapturedTexture>,
+}
+
+impl FrameCapture {
+  pub fn new(draw_data: &DrawData) -> FrameCapture {
+    let draw_lists = draw_data
+      .draw_lists()
+      .map(|draw_list| CapturedDrawList {
+        vtx_buffer: draw_list.vtx_buffer().to_vec(),
+        idx_buffer: draw_list.idx_buffer().to_vec(),
+        commands: draw_list
+          .commands()
+          .map(|cmd| match cmd {
+            DrawCmd::Elements { count, cmd_params } => {
+              CapturedCmd::Elements {
+                count,
+                params: CapturedParams {
+                  clip_rect: cmd_params.clip_rect,
+                  texture_id: cmd_params.texture_id.id(),
+                  vtx_offset: cmd_params.vtx_offset,
+                  idx_offset: cmd_params.idx_offset,
+                },
+              }
+            },
+            DrawCmd::ResetRenderState => CapturedCmd::ResetRenderState,
+            DrawCmd::RawCallback { .. } => CapturedCmd::Callback,
+          })
+          .collect(),
+      })
+      .collect();
+
+    FrameCapture {
+      display_pos: draw_data.display_pos,
+      display_size: draw_data.display_size,
+      framebuffer_scale: draw_data.framebuffer_scale,
+      draw_lists,
+      font_atlas: None,
+    }
+  }
+
+  // points every command drawing `from` at `to`, e.g. at the font atlas
+  // uploaded by a replay viewer
+  pub fn remap_texture(&mut self, from: TextureId, to: TextureId) {
+    for draw_list in &mut self.draw_lists {
+      for cmd in &mut draw_list.commands {
+        if let CapturedCmd::Elements { params, .. } = cmd {
+          if params.texture_id == from.id() {
+            params.texture_id = to.id();
+          }
+        }
+      }
+    }
+    if let Some(atlas) = &mut self.font_atlas {
+      if atlas.id == from.id() {
+        atlas.id = to.id();
+      }
+    }
+  }
+
+  // human readable list of the structural differences to `other`, empty
+  // when both frames would draw the same thing
+  pub fn diff(&self, other: &FrameCapture) -> Vec<String> {
+    let mut diffs = Vec::new();
+    let d = &mut diffs;
+
+    diff_field(d, "display_pos", &self.display_pos, &other.display_pos);
+    diff_field(d, "display_size", &self.display_size, &other.display_size);
+    diff_field(
+      d,
+      "framebuffer_scale",
+      &self.framebuffer_scale,
+      &other.framebuffer_scale,
+    );
+    diff_field(
+      d,
+      "draw_lists.len",
+      &self.draw_lists.len(),
+      &other.draw_lists.len(),
+    );
+
+    let lists = self.draw_lists.iter().zip(&other.draw_lists);
+    for (i, (a, b)) in lists.enumerate() {
+      diff_field(
+        d,
+        &format!("draw_lists[{i}].vtx_buffer.len"),
+        &a.vtx_buffer.len(),
+        &b.vtx_buffer.len(),
+      );
+      let mut vertices = a.vtx_buffer.iter().zip(&b.vtx_buffer);
+      if let Some(j) = vertices.position(|(a, b)| !same_vertex(a, b)) {
+        d.push(format!("draw_lists[{i}].vtx_buffer[{j}] differs"));
+      }
+
+      diff_field(
+        d,
+        &format!("draw_lists[{i}].idx_buffer.len"),
+        &a.idx_buffer.len(),
+        &b.idx_buffer.len(),
+      );
+      let mut indices = a.idx_buffer.iter().zip(&b.idx_buffer);
+      if let Some(j) = indices.position(|(a, b)| a != b) {
+        d.push(format!("draw_lists[{i}].idx_buffer[{j}] differs"));
+      }
+
+      diff_field(
+        d,
+        &format!("draw_lists[{i}].commands"),
+        &a.commands,
+        &b.commands,
+      );
+    }
+
+    let atlas = |frame: &FrameCapture| {
+      frame.font_atlas.as_ref().map(|t| (t.id, t.width, t.height))
+    };
+    diff_field(d, "font_atlas", &atlas(self), &atlas(other));
+    if let (Some(a), Some(b)) = (&self.font_atlas, &other.font_atlas) {
+      if a.data != b.data {
+        d.push("font_atlas.data differs".to_string());
+      }
+    }
+
+    diffs
+  }
+
+  pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    self.write_to(&mut file)?;
+    file.flush()
+  }
+
+  pub fn load(path: impl AsRef<Path>) -> io::Result<FrameCapture> {
+    FrameCapture::read_from(&mut BufReader::new(File::open(path)?))
+  }
+
+  // little endian, see `read_from` for the layout
+  pub fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+    w.write_all(MAGIC)?;
+    write_u32(w, VERSION)?;
+    for v in [self.display_pos, self.display_size, self.framebuffer_scale] {
+      write_f32s(w, &v)?;
+    }
+
+    write_len(w, self.draw_lists.len())?;
+    for draw_list in &self.draw_lists {
+      write_len(w, draw_list.vtx_buffer.len())?;
+      for vertex in &draw_list.vtx_buffer {
+        write_f32s(w, &vertex.pos)?;
+        write_f32s(w, &vertex.uv)?;
+        w.write_all(&vertex.col)?;
+      }
+
+      write_len(w, draw_list.idx_buffer.len())?;
+      for index in &draw_list.idx_buffer {
+        w.write_all(&index.to_le_bytes())?;
+      }
+
+      write_len(w, draw_list.commands.len())?;
+      for cmd in &draw_list.commands {
+        match cmd {
+          CapturedCmd::Elements { count, params } => {
+            w.write_all(&[0])?;
+            write_len(w, *count)?;
+            write_f32s(w, &params.clip_rect)?;
+            w.write_all(&(params.texture_id as u64).to_le_bytes())?;
+            write_len(w, params.vtx_offset)?;
+            write_len(w, params.idx_offset)?;
+          },
+          CapturedCmd::ResetRenderState => w.write_all(&[1])?,
+          CapturedCmd::Callback => w.write_all(&[2])?,
+        }
+      }
+    }
+
+    match &self.font_atlas {
+      Some(atlas) => {
+        w.write_all(&[1])?;
+        w.write_all(&(atlas.id as u64).to_le_bytes())?;
+        write_len(w, atlas.width)?;
+        write_len(w, atlas.height)?;
+        write_len(w, atlas.data.len())?;
+        w.write_all(&atlas.data)?;
+      },
+      None => w.write_all(&[0])?,
+    }
+    Ok(())
+  }
+
+  pub fn read_from(r: &mut impl Read) -> io::Result<FrameCapture> {
+    let mut magic = [0; 4];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+      return Err(invalid("not a frame capture"));
+    }
+    if read_u32(r)? != VERSION {
+      return Err(invalid("unsupported frame capture version"));
+    }
+
+    let mut frame = FrameCapture {
+      display_pos: read_f32s(r)?,
+      display_size: read_f32s(r)?,
+      framebuffer_scale: read_f32s(r)?,
+      ..Default::default()
+    };
+
+    // buffers grow as they are read, so a corrupt count runs into the end
+    // of the input instead of allocating for it up front
+    for _ in 0..read_len(r)? {
+      let mut draw_list = CapturedDrawList::default();
+      for _ in 0..read_len(r)? {
+        let pos = read_f32s(r)?;
+        let uv = read_f32s(r)?;
+        let mut col = [0; 4];
+        r.read_exact(&mut col)?;
+        draw_list.vtx_buffer.push(DrawVert { pos, uv, col });
+      }
+
+      for _ in 0..read_len(r)? {
+        let mut index = [0; 2];
+        r.read_exact(&mut index)?;
+        draw_list.idx_buffer.push(u16::from_le_bytes(index));
+      }
+
+      for _ in 0..read_len(r)? {
+        let cmd = match read_u8(r)? {
+          0 => CapturedCmd::Elements {
+            count: read_len(r)?,
+            params: CapturedParams {
+              clip_rect: read_f32s(r)?,
+              texture_id: read_u64(r)? as usize,
+              vtx_offset: read_len(r)?,
+              idx_offset: read_len(r)?,
+            },
+          },
+          1 => CapturedCmd::ResetRenderState,
+          2 => CapturedCmd::Callback,
+          _ => return Err(invalid("unknown draw command")),
+        };
+        if let CapturedCmd::Elements { count, params } = &cmd {
+          if !draw_list.in_range(*count, params) {
+            return Err(invalid("draw command outside of its buffers"));
+          }
+        }
+        draw_list.commands.push(cmd);
+      }
+      frame.draw_lists.push(draw_list);
+    }
+
+    if read_u8(r)? == 1 {
+      let id = read_u64(r)? as usize;
+      let width = read_len(r)?;
+      let height = read_len(r)?;
+      let len = read_len(r)?;
+      let expected = (width <= MAX_TEXTURE_SIZE && height <= MAX_TEXTURE_SIZE)
+        .then(|| width * height * 4);
+      if expected != Some(len) {
+        return Err(invalid("font atlas size doesn't match its data"));
+      }
+      let mut data = Vec::new();
+      r.take(len as u64).read_to_end(&mut data)?;
+      if data.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+      }
+      frame.font_atlas = Some(CapturedTexture { id, width, height, data });
+    }
+    Ok(frame)
+  }
+}
+
+fn diff_field<T: PartialEq + Debug>(
+  diffs: &mut Vec<String>,
+  name: &str,
+  a: &T,
+  b: &T,
+) {
+  if a != b {
+    diffs.push(format!("{name}: {a:?} != {b:?}"));
+  }
+}
+
+fn same_vertex(a: &DrawVert, b: &DrawVert) -> bool {
+  a.pos == b.pos && a.uv == b.uv && a.col == b.col
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_u32(w: &mut impl Write, value: u32) -> io::Result<()> {
+  w.write_all(&value.to_le_bytes())
+}
+
+fn write_len(w: &mut impl Write, len: usize) -> io::Result<()> {
+  write_u32(w, len as u32)
+}
+
+fn write_f32s(w: &mut impl Write, values: &[f32]) -> io::Result<()> {
+  for value in values {
+    w.write_all(&value.to_le_bytes())?;
+  }
+  Ok(())
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+  let mut bytes = [0; 1];
+  r.read_exact(&mut bytes)?;
+  Ok(bytes[0])
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+  let mut bytes = [0; 4];
+  r.read_exact(&mut bytes)?;
+  Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+  let mut bytes = [0; 8];
+  r.read_exact(&mut bytes)?;
+  Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_len(r: &mut impl Read) -> io::Result<usize> {
+  read_u32(r).map(|len| len as usize)
+}
+
+fn read_f32s<const N: usize>(r: &mut impl Read) -> io::Result<[f32; N]> {
+  let mut values = [0.0; N];
+  for value in &mut values {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    *value = f32::from_le_bytes(bytes);
+  }
+  Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn frame() -> FrameCapture {
+    let vertex = |x: f32, y: f32| DrawVert {
+      pos: [x, y],
+      uv: [x / 10.0, y / 10.0],
+      col: [255, 128, 0, 255],
+    };
+    let params = CapturedParams {
+      clip_rect: [0.0, 0.0, 10.0, 10.0],
+      texture_id: 1,
+      vtx_offset: 0,
+      idx_offset: 0,
+    };
+    FrameCapture {
+      display_pos: [0.0, 0.0],
+      display_size: [10.0, 10.0],
+      framebuffer_scale: [2.0, 2.0],
+      draw_lists: vec![CapturedDrawList {
+        vtx_buffer: vec![
+          vertex(0.0, 0.0),
+          vertex(10.0, 0.0),
+          vertex(10.0, 10.0),
+          vertex(0.0, 10.0),
+        ],
+        idx_buffer: vec![0, 1, 2, 0, 2, 3],
+        commands: vec![
+          CapturedCmd::Elements { count: 3, params },
+          CapturedCmd::ResetRenderState,
+          CapturedCmd::Callback,
+          CapturedCmd::Elements {
+            count: 3,
+            params: CapturedParams { idx_offset: 3, ..params },
+          },
+        ],
+      }],
+      font_atlas: Some(CapturedTexture {
+        id: 1,
+        width: 2,
+        height: 1,
+        data: vec![255, 255, 255, 255, 0, 0, 0, 0],
+      }),
+    }
+  }
+
+  fn bytes(frame: &FrameCapture) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    frame.write_to(&mut bytes).unwrap();
+    bytes
+  }
+
+  fn read(bytes: &[u8]) -> io::Result<FrameCapture> {
+    FrameCapture::read_from(&mut &bytes[..])
+  }
+
+  #[test]
+  fn round_trips() {
+    let frame = frame();
+    let read = read(&bytes(&frame)).unwrap();
+    assert_eq!(frame.diff(&read), Vec::<String>::new());
+    assert_eq!(read.font_atlas, frame.font_atlas);
+  }
+
+  #[test]
+  fn rejects_truncated_input() {
+    let bytes = bytes(&frame());
+    for len in 0..bytes.len() {
+      let error = read(&bytes[..len]).err().expect("truncated read succeeded");
+      assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "at {len}");
+    }
+  }
+
+  #[test]
+  fn rejects_corrupt_input() {
+    let corrupt = |change: fn(&mut FrameCapture)| {
+      let mut frame = frame();
+      change(&mut frame);
+      read(&bytes(&frame)).err().map(|error| error.kind())
+    };
+    let invalid = Some(io::ErrorKind::InvalidData);
+
+    assert_eq!(
+      corrupt(|f| f.font_atlas.as_mut().unwrap().data.push(0)),
+      invalid
+    );
+    assert_eq!(
+      corrupt(|f| f.font_atlas.as_mut().unwrap().width = 1 << 20),
+      invalid
+    );
+    assert_eq!(corrupt(|f| f.draw_lists[0].idx_buffer.truncate(4)), invalid);
+    assert_eq!(corrupt(|f| f.draw_lists[0].idx_buffer[5] = 4), invalid);
+    assert_eq!(corrupt(|f| f.draw_lists[0].vtx_buffer.truncate(3)), invalid);
+
+    let mut bytes = bytes(&frame());
+    bytes[0] = b'X';
+    assert_eq!(read(&bytes).err().map(|error| error.kind()), invalid);
+  }
+}
@@ -0,0 +1,69 @@
+use raylib::ffi::{self, TraceLogLevel};
+use std::ffi::CString;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+  // a font file or font data that couldn't be read or isn't a font, `path`
+  // is unset for fonts added from memory
+  Font { path: Option<PathBuf>, error: io::Error },
+  // rlgl couldn't create the texture the font atlas is uploaded to
+  FontTexture { width: i32, height: i32 },
+  // the imgui ini file couldn't be written
+  SaveSettings { path: PathBuf, error: io::Error },
+  // imgui drew a texture id that isn't in `RaylibGui::textures`, reported
+  // once per id in debug builds
+  UnregisteredTexture { id: usize },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::Font { path: Some(path), error } => {
+        write!(f, "loading font {}: {}", path.display(), error)
+      },
+      Error::Font { path: None, error } => write!(f, "loading font: {error}"),
+      Error::FontTexture { width, height } => {
+        write!(f, "uploading the {width}x{height} font atlas failed")
+      },
+      Error::SaveSettings { path, error } => {
+        write!(f, "saving {}: {}", path.display(), error)
+      },
+      Error::UnregisteredTexture { id } => {
+        write!(f, "texture {id} is drawn by imgui but not registered")
+      },
+    }
+  }
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::Font { error, .. } | Error::SaveSettings { error, .. } => {
+        Some(error)
+      },
+      Error::FontTexture { .. } | Error::UnregisteredTexture { .. } => None,
+    }
+  }
+}
+
+// where errors go that happen outside of a call that could return them,
+// like saving settings on drop or reloading a watched font
+pub(crate) type ErrorHook = Box<dyn FnMut(&Error)>;
+
+// the default hook, a warning through raylib's own logging
+pub(crate) fn log_error(error: &Error) {
+  if let Ok(text) = CString::new(format!("IMGUI: {error}")) {
+    unsafe {
+      ffi::TraceLog(
+        TraceLogLevel::LOG_WARNING as i32,
+        c"%s".as_ptr(),
+        text.as_ptr(),
+      );
+    }
+  }
+}
@@ -0,0 +1,560 @@
+use crate::error::{Error, Result};
+use crate::icons::IconFont;
+use crate::raylib_font::RaylibGlyphs;
+use imgui::{Context, FontConfig, FontGlyphRanges, FontSource};
+use raylib::ffi;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+// a codepoint the embedded font doesn't have, so the font a raylib font is
+// added as starts out without glyphs
+const NO_GLYPHS: &[u32] = &[0x1, 0x1, 0];
+
+// seconds between two looks at the font files while they are watched
+const WATCH_INTERVAL: f32 = 0.5;
+
+// stays the same across atlas rebuilds, unlike `imgui::FontId`, which
+// `RaylibGui::font` turns it into
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct FontId(usize);
+
+impl FontId {
+  pub(crate) const DEFAULT: FontId = FontId(0);
+}
+
+#[derive(Clone)]
+pub(crate) enum FontData {
+  Default,
+  File(PathBuf),
+  Memory(Vec<u8>),
+  Raylib(Rc<RaylibGlyphs>),
+}
+
+#[derive(Clone)]
+pub(crate) struct StackSource {
+  pub data: FontData,
+  pub config: FontConfig,
+}
+
+// one imgui font built from several ttf/otf sources, every source after
+// the first one is merged into it, e.g. to add icons or other scripts.
+// options like `oversample` apply to the source added last
+#[derive(Clone)]
+pub struct FontStack {
+  sources: Vec<StackSource>,
+}
+
+impl FontStack {
+  // imgui's embedded proggy clean, 13px unless `size_pixels` is set
+  pub fn default_font() -> FontStack {
+    FontStack::with(FontData::Default, 0.0)
+  }
+
+  pub fn from_file(path: impl AsRef<Path>, size_pixels: f32) -> FontStack {
+    FontStack::with(FontData::File(path.as_ref().into()), size_pixels)
+  }
+
+  pub fn from_memory(data: impl Into<Vec<u8>>, size_pixels: f32) -> FontStack {
+    FontStack::with(FontData::Memory(data.into()), size_pixels)
+  }
+
+  // copies the glyph images and metrics of a raylib font, including bitmap
+  // and image fonts and `get_font_default`, at the font's base size. the
+  // font can be unloaded afterwards
+  pub fn from_raylib(font: &impl AsRef<ffi::Font>) -> FontStack {
+    let glyphs = unsafe { RaylibGlyphs::from_font(font.as_ref()) };
+    let size = glyphs.size;
+    FontStack::with(FontData::Raylib(Rc::new(glyphs)), size)
+      .glyph_ranges(FontGlyphRanges::from_slice(NO_GLYPHS))
+  }
+
+  pub fn merge_file(self, path: impl AsRef<Path>, size_pixels: f32) -> Self {
+    self.merge(FontData::File(path.as_ref().into()), size_pixels)
+  }
+
+  pub fn merge_memory(
+    self,
+    data: impl Into<Vec<u8>>,
+    size_pixels: f32,
+  ) -> Self {
+    self.merge(FontData::Memory(data.into()), size_pixels)
+  }
+
+  pub fn size_pixels(mut self, size_pixels: f32) -> Self {
+    self.last().size_pixels = size_pixels;
+    self
+  }
+
+  // how many times wider/taller glyphs are rasterized before they are
+  // downsampled into the atlas
+  pub fn oversample(mut self, horizontal: i32, vertical: i32) -> Self {
+    let config = self.last();
+    config.oversample_h = horizontal;
+    config.oversample_v = vertical;
+    self
+  }
+
+  pub fn glyph_ranges(mut self, ranges: FontGlyphRanges) -> Self {
+    self.last().glyph_ranges = ranges;
+    self
+  }
+
+  // merges an icon font sized to the first source of the stack
+  pub fn merge_icons(mut self, icons: &IconFont) -> Self {
+    let source = icons.source(self.base_size());
+    self.sources.push(source);
+    self
+  }
+
+  pub fn pixel_snap(mut self, snap: bool) -> Self {
+    self.last().pixel_snap_h = snap;
+    self
+  }
+
+  // anything not covered by the options above
+  pub fn config(mut self, f: impl FnOnce(&mut FontConfig)) -> Self {
+    f(self.last());
+    self
+  }
+
+  fn with(data: FontData, size_pixels: f32) -> FontStack {
+    FontStack { sources: Vec::new() }.merge(data, size_pixels)
+  }
+
+  fn merge(mut self, data: FontData, size_pixels: f32) -> Self {
+    let config = FontConfig { size_pixels, ..FontConfig::default() };
+    self.sources.push(StackSource { data, config });
+    self
+  }
+
+  fn last(&mut self) -> &mut FontConfig {
+    &mut self.sources.last_mut().unwrap().config
+  }
+
+  pub(crate) fn base_size(&self) -> f32 {
+    size_or_default(&self.sources[0].config)
+  }
+
+  // the font files, read in the order the sources were added
+  fn load(&self) -> Result<Vec<Option<Vec<u8>>>> {
+    self
+      .sources
+      .iter()
+      .map(|source| match &source.data {
+        FontData::Default | FontData::Raylib(_) => Ok(None),
+        FontData::File(path) => read_font(path).map(Some),
+        FontData::Memory(data) => match check_font(data) {
+          Ok(()) => Ok(Some(data.clone())),
+          Err(error) => Err(Error::Font { path: None, error }),
+        },
+      })
+      .collect()
+  }
+
+  fn modified(&self) -> Vec<Option<SystemTime>> {
+    self
+      .sources
+      .iter()
+      .map(|source| match &source.data {
+        FontData::File(path) => modified(path),
+        _ => None,
+      })
+      .collect()
+  }
+}
+
+struct FontEntry {
+  stack: FontStack,
+  // what `FontStack::load` read, kept so the atlas can be rebuilt without
+  // touching the files again
+  data: Vec<Option<Vec<u8>>>,
+  id: imgui::FontId,
+  // custom rects of the raylib glyphs in the stack
+  rects: Vec<(Rc<RaylibGlyphs>, Vec<i32>)>,
+  // when the file of each source was last changed
+  modified: Vec<Option<SystemTime>>,
+}
+
+pub(crate) struct Fonts {
+  entries: Vec<FontEntry>,
+  // pixels per font pixel the atlas is baked at, the ui scale times the
+  // framebuffer scale
+  pub density: f32,
+  // size of the uploaded atlas texture
+  pub atlas_size: [i32; 2],
+  pub watch: bool,
+  since_poll: f32,
+  // watched files that changed but couldn't be loaded, their fonts keep
+  // the data they had before
+  pub errors: Vec<Error>,
+}
+
+impl Default for Fonts {
+  fn default() -> Fonts {
+    Fonts {
+      entries: Vec::new(),
+      density: 1.0,
+      atlas_size: [0, 0],
+      watch: false,
+      since_poll: 0.0,
+      errors: Vec::new(),
+    }
+  }
+}
+
+impl Fonts {
+  // adds the font to the atlas, which has to be rebuilt afterwards
+  pub fn add(&mut self, ctx: &mut Context, stack: FontStack) -> Result<FontId> {
+    let modified = stack.modified();
+    let data = stack.load()?;
+    let id = add_to_atlas(ctx, &stack, &data, self.density);
+    let rects = register_glyphs(ctx, &stack, self.density);
+    self.entries.push(FontEntry { stack, data, id, rects, modified });
+    Ok(FontId(self.entries.len() - 1))
+  }
+
+  // imgui only merges into the font added last, so every font is added
+  // again with `icons` appended to the sources of `font`
+  pub fn merge_icons(
+    &mut self,
+    ctx: &mut Context,
+    font: FontId,
+    icons: &IconFont,
+  ) -> Result<()> {
+    let entry = &mut self.entries[font.0];
+    let source = icons.source(entry.stack.base_size());
+    let stack = FontStack { sources: vec![source] };
+    let data = stack.load()?;
+    entry.modified.extend(stack.modified());
+    entry.stack.sources.extend(stack.sources);
+    entry.data.extend(data);
+    self.rebuild(ctx);
+    Ok(())
+  }
+
+  pub fn rebuild(&mut self, ctx: &mut Context) {
+    ctx.fonts().clear();
+    for entry in &mut self.entries {
+      entry.id = add_to_atlas(ctx, &entry.stack, &entry.data, self.density);
+      entry.rects = register_glyphs(ctx, &entry.stack, self.density);
+    }
+  }
+
+  // raylib glyphs are drawn into the atlas after imgui packed it, before
+  // it is uploaded
+  pub fn blit_glyphs(&self, ctx: &mut Context) {
+    for entry in &self.entries {
+      for (glyphs, rects) in &entry.rects {
+        unsafe { glyphs.blit(ctx, rects, self.density) };
+      }
+    }
+  }
+
+  // reads font files whose modification time changed since they were
+  // loaded, returns whether any font has new data and the atlas has to be
+  // rebuilt
+  pub fn poll(&mut self, delta_time: f32) -> bool {
+    self.since_poll += delta_time;
+    if !self.watch || self.since_poll < WATCH_INTERVAL {
+      return false;
+    }
+    self.since_poll = 0.0;
+
+    let mut changed = false;
+    for entry in &mut self.entries {
+      for (i, source) in entry.stack.sources.iter().enumerate() {
+        let FontData::File(path) = &source.data else { continue };
+        let modified = modified(path);
+        if modified.is_none() || modified == entry.modified[i] {
+          continue;
+        }
+        // not retried until the file changes again, an editor that is
+        // still writing it changes it again soon
+        entry.modified[i] = modified;
+        match read_font(path) {
+          Ok(data) => {
+            entry.data[i] = Some(data);
+            changed = true;
+          },
+          Err(error) => self.errors.push(error),
+        }
+      }
+    }
+    changed
+  }
+
+  pub fn get(&self, id: FontId) -> imgui::FontId {
+    self.entries[id.0].id
+  }
+}
+
+fn add_to_atlas(
+  ctx: &mut Context,
+  stack: &FontStack,
+  data: &[Option<Vec<u8>>],
+  density: f32,
+) -> imgui::FontId {
+  let sources: Vec<FontSource> = stack
+    .sources
+    .iter()
+    .zip(data)
+    .map(|(source, data)| {
+      let config = scaled(&source.config, density);
+      match data {
+        Some(data) => FontSource::TtfData {
+          data,
+          size_pixels: config.size_pixels,
+          config: Some(config),
+        },
+        None => FontSource::DefaultFontData { config: Some(config) },
+      }
+    })
+    .collect();
+  ctx.fonts().add_font(&sources)
+}
+
+fn register_glyphs(
+  ctx: &mut Context,
+  stack: &FontStack,
+  density: f32,
+) -> Vec<(Rc<RaylibGlyphs>, Vec<i32>)> {
+  let mut rects = Vec::new();
+  for source in &stack.sources {
+    if let FontData::Raylib(glyphs) = &source.data {
+      let indices = unsafe { glyphs.register(ctx, density) };
+      rects.push((glyphs.clone(), indices));
+    }
+  }
+  rects
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+  std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn read_font(path: &Path) -> Result<Vec<u8>> {
+  let font_error = |error| Error::Font { path: Some(path.into()), error };
+  let data = std::fs::read(path).map_err(font_error)?;
+  check_font(&data).map_err(font_error)?;
+  Ok(data)
+}
+
+// imgui asserts on data stb_truetype can't open, and stb_truetype reads
+// the tables it needs without bounds checks, so those are checked up front
+fn check_font(data: &[u8]) -> io::Result<()> {
+  let invalid =
+    |what: &str| io::Error::new(io::ErrorKind::InvalidData, what.to_owned());
+
+  let offset = match data.get(0..4) {
+    Some(b"ttcf") => match be32(data, 12) {
+      Some(offset) => offset,
+      None => return Err(invalid("truncated font collection header")),
+    },
+    Some([0, 1, 0, 0] | b"OTTO" | b"true") => 0,
+    _ => return Err(invalid("not a truetype or opentype font")),
+  };
+  let Some(count) = be16(data, offset + 4) else {
+    return Err(invalid("truncated font header"));
+  };
+  if offset + 12 + count * 16 > data.len() {
+    return Err(invalid("truncated table directory"));
+  }
+
+  let mut tables = Vec::with_capacity(count);
+  for record in (0..count).map(|table| offset + 12 + table * 16) {
+    let tag = &data[record..record + 4];
+    let start = be32(data, record + 8).unwrap_or_default();
+    let length = be32(data, record + 12).unwrap_or_default();
+    match data.get(start..start + length) {
+      Some(contents) => tables.push((tag, contents)),
+      None => return Err(invalid("table past the end of the file")),
+    }
+  }
+  let table = |name: &[u8; 4]| {
+    tables
+      .iter()
+      .find(|(tag, _)| *tag == &name[..])
+      .map(|(_, contents)| *contents)
+  };
+  let required = |name: &[u8; 4]| match table(name) {
+    Some(contents) => Ok(contents),
+    None => {
+      Err(invalid(&format!("missing {} table", String::from_utf8_lossy(name))))
+    },
+  };
+
+  // the fixed size parts of the tables stb_truetype reads
+  for (name, size) in [(b"cmap", 4), (b"head", 54), (b"hhea", 36)] {
+    if required(name)?.len() < size {
+      let name = String::from_utf8_lossy(name);
+      return Err(invalid(&format!("truncated {name} table")));
+    }
+  }
+  required(b"hmtx")?;
+
+  let cmap = required(b"cmap")?;
+  let encodings = be16(cmap, 2).unwrap_or_default();
+  for record in (0..encodings).map(|encoding| 4 + encoding * 8) {
+    match be32(cmap, record + 4) {
+      Some(subtable) if subtable + 4 <= cmap.len() => {},
+      _ => return Err(invalid("cmap subtable past the end of the table")),
+    }
+  }
+
+  // opentype fonts with cff outlines have no glyf and loca tables
+  if table(b"CFF ").is_some() {
+    return Ok(());
+  }
+  let (loca, glyf) = (required(b"loca")?, required(b"glyf")?);
+  // stb_truetype assumes the whole 16 bit range without a glyph count
+  let glyphs = table(b"maxp").and_then(|maxp| be16(maxp, 4)).unwrap_or(0xffff);
+  let long = be16(required(b"head")?, 50) != Some(0);
+  let entry = if long { 4 } else { 2 };
+  if loca.len() < (glyphs + 1) * entry {
+    return Err(invalid("truncated loca table"));
+  }
+  for glyph in 0..=glyphs {
+    let end = if long {
+      be32(loca, glyph * 4)
+    } else {
+      be16(loca, glyph * 2).map(|offset| offset * 2)
+    };
+    if end.unwrap_or_default() > glyf.len() {
+      return Err(invalid("glyph past the end of the glyf table"));
+    }
+  }
+  Ok(())
+}
+
+fn be16(data: &[u8], at: usize) -> Option<usize> {
+  data.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+}
+
+fn be32(data: &[u8], at: usize) -> Option<usize> {
+  let b = data.get(at..at + 4)?;
+  Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+}
+
+// imgui falls back to 13px for the default font without a size
+fn size_or_default(config: &FontConfig) -> f32 {
+  match config.size_pixels {
+    size if size > 0.0 => size,
+    _ => 13.0,
+  }
+}
+
+fn scaled(config: &FontConfig, density: f32) -> FontConfig {
+  FontConfig {
+    size_pixels: size_or_default(config) * density,
+    glyph_offset: config.glyph_offset.map(|offset| offset * density),
+    glyph_extra_spacing: config.glyph_extra_spacing.map(|s| s * density),
+    glyph_min_advance_x: config.glyph_min_advance_x * density,
+    ..config.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // an sfnt file with the given tables, each right after the directory
+  fn font_file(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut data = vec![0, 1, 0, 0];
+    data.extend((tables.len() as u16).to_be_bytes());
+    data.extend([0; 6]);
+    let mut start = 12 + tables.len() * 16;
+    for (tag, contents) in tables {
+      data.extend(*tag);
+      data.extend([0; 4]);
+      data.extend((start as u32).to_be_bytes());
+      data.extend((contents.len() as u32).to_be_bytes());
+      start += contents.len();
+    }
+    for (_, contents) in tables {
+      data.extend(contents);
+    }
+    data
+  }
+
+  // the smallest tables that pass, one empty glyph and no cmap subtables
+  fn minimal_tables() -> Vec<(&'static [u8; 4], Vec<u8>)> {
+    vec![
+      (b"cmap", vec![0; 4]),
+      (b"head", vec![0; 54]),
+      (b"hhea", vec![0; 36]),
+      (b"hmtx", vec![]),
+      (b"maxp", vec![0, 0, 0x50, 0, 0, 1]),
+      (b"loca", vec![0; 4]),
+      (b"glyf", vec![]),
+    ]
+  }
+
+  fn font_with(tag: &[u8; 4], contents: Vec<u8>) -> Vec<u8> {
+    let mut tables = minimal_tables();
+    tables.iter_mut().find(|(t, _)| *t == tag).unwrap().1 = contents;
+    font_file(&tables)
+  }
+
+  fn font_error(result: Result<Vec<u8>>) -> io::ErrorKind {
+    match result {
+      Err(Error::Font { path: Some(_), error }) => error.kind(),
+      Err(error) => panic!("unexpected error {error}"),
+      Ok(_) => panic!("read a font"),
+    }
+  }
+
+  #[test]
+  fn checks_font_headers() {
+    let tables = minimal_tables();
+    assert!(check_font(&font_file(&tables)).is_ok());
+
+    let missing = check_font(&font_file(&tables[1..])).unwrap_err();
+    assert_eq!(missing.kind(), io::ErrorKind::InvalidData);
+
+    let mut past_end = font_file(&tables);
+    past_end[12 + 12..12 + 16].copy_from_slice(&1000u32.to_be_bytes());
+    assert!(check_font(&past_end).is_err());
+  }
+
+  #[test]
+  fn rejects_truncated_files() {
+    let font = font_file(&minimal_tables());
+    for len in [3, 11, 12 + 16 * 7 - 1, font.len() - 1] {
+      assert!(check_font(&font[..len]).is_err(), "truncated to {len}");
+    }
+  }
+
+  #[test]
+  fn rejects_tables_too_short_to_read() {
+    assert!(check_font(&font_with(b"head", vec![0; 53])).is_err());
+    assert!(check_font(&font_with(b"hhea", vec![0; 35])).is_err());
+
+    // one encoding record whose subtable starts past the table
+    let cmap = [vec![0, 0, 0, 1, 0, 3, 0, 1], 64u32.to_be_bytes().to_vec()];
+    assert!(check_font(&font_with(b"cmap", cmap.concat())).is_err());
+
+    // three glyphs need four short loca entries
+    let maxp = vec![0, 0, 0x50, 0, 0, 3];
+    assert!(check_font(&font_with(b"maxp", maxp)).is_err());
+    // the one glyph ends 8 bytes into an empty glyf table
+    assert!(check_font(&font_with(b"loca", vec![0, 0, 0, 4])).is_err());
+  }
+
+  #[test]
+  fn rejects_missing_files() {
+    let path = std::env::temp_dir().join("raylib_imgui_no_such_font.ttf");
+    assert_eq!(font_error(read_font(&path)), io::ErrorKind::NotFound);
+  }
+
+  #[test]
+  fn rejects_files_that_are_not_fonts() {
+    let path = std::env::temp_dir()
+      .join(format!("raylib_imgui_not_a_font_{}.ttf", std::process::id()));
+    std::fs::write(&path, "not a font, just some text").unwrap();
+    let result = read_font(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(font_error(result), io::ErrorKind::InvalidData);
+  }
+}
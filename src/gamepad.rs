@@ -0,0 +1,479 @@
+use crate::InputSource;
+use imgui::{Io, Key};
+use raylib::prelude::*;
+
+// raylib's MAX_GAMEPADS
+const MAX_GAMEPADS: i32 = 4;
+const DEFAULT_DEAD_ZONE: f32 = 0.20;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GamepadSelect {
+  // whichever pad last pressed a button or moved a stick
+  #[default]
+  LastActive,
+  Index(i32),
+}
+
+// which face button confirms and which cancels. raylib names face buttons
+// by position, imgui activates with `GamepadFaceDown` and cancels with
+// `GamepadFaceRight`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GamepadLayout {
+  // a on the bottom confirms, b on the right cancels
+  #[default]
+  Xbox,
+  // cross on the bottom confirms, circle on the right cancels
+  PlayStation,
+  // a on the right confirms, b on the bottom cancels
+  Nintendo,
+}
+
+// which raylib gamepad button drives which imgui key, `GamepadInput::buttons`
+#[derive(Clone)]
+pub struct GamepadMap {
+  buttons: Vec<(GamepadButton, Key)>,
+}
+
+impl Default for GamepadMap {
+  fn default() -> GamepadMap {
+    GamepadMap::layout(GamepadLayout::default())
+  }
+}
+
+impl GamepadMap {
+  pub fn new() -> GamepadMap {
+    GamepadMap::default()
+  }
+
+  pub fn empty() -> GamepadMap {
+    GamepadMap { buttons: Vec::new() }
+  }
+
+  pub fn layout(layout: GamepadLayout) -> GamepadMap {
+    use GamepadButton::*;
+
+    let (confirm, cancel) = match layout {
+      GamepadLayout::Xbox | GamepadLayout::PlayStation => {
+        (GAMEPAD_BUTTON_RIGHT_FACE_DOWN, GAMEPAD_BUTTON_RIGHT_FACE_RIGHT)
+      },
+      GamepadLayout::Nintendo => {
+        (GAMEPAD_BUTTON_RIGHT_FACE_RIGHT, GAMEPAD_BUTTON_RIGHT_FACE_DOWN)
+      },
+    };
+    GamepadMap {
+      buttons: vec![
+        (GAMEPAD_BUTTON_LEFT_FACE_UP, Key::GamepadDpadUp),
+        (GAMEPAD_BUTTON_LEFT_FACE_RIGHT, Key::GamepadDpadRight),
+        (GAMEPAD_BUTTON_LEFT_FACE_DOWN, Key::GamepadDpadDown),
+        (GAMEPAD_BUTTON_LEFT_FACE_LEFT, Key::GamepadDpadLeft),
+        (GAMEPAD_BUTTON_RIGHT_FACE_UP, Key::GamepadFaceUp),
+        (GAMEPAD_BUTTON_RIGHT_FACE_LEFT, Key::GamepadFaceLeft),
+        (confirm, Key::GamepadFaceDown),
+        (cancel, Key::GamepadFaceRight),
+        (GAMEPAD_BUTTON_LEFT_TRIGGER_1, Key::GamepadL1),
+        (GAMEPAD_BUTTON_LEFT_TRIGGER_2, Key::GamepadL2),
+        (GAMEPAD_BUTTON_RIGHT_TRIGGER_1, Key::GamepadR1),
+        (GAMEPAD_BUTTON_RIGHT_TRIGGER_2, Key::GamepadR2),
+        (GAMEPAD_BUTTON_LEFT_THUMB, Key::GamepadL3),
+        (GAMEPAD_BUTTON_RIGHT_THUMB, Key::GamepadR3),
+        (GAMEPAD_BUTTON_MIDDLE_LEFT, Key::GamepadBack),
+        (GAMEPAD_BUTTON_MIDDLE_RIGHT, Key::GamepadStart),
+      ],
+    }
+  }
+
+  // replaces what `button` was mapped to before
+  pub fn set(&mut self, button: GamepadButton, key: Key) {
+    match self.buttons.iter_mut().find(|(b, _)| *b == button) {
+      Some(entry) => entry.1 = key,
+      None => self.buttons.push((button, key)),
+    }
+  }
+
+  pub fn remove(&mut self, button: GamepadButton) {
+    self.buttons.retain(|(b, _)| *b != button);
+  }
+
+  pub fn get(&self, button: GamepadButton) -> Option<Key> {
+    self.buttons.iter().find(|(b, _)| *b == button).map(|(_, key)| *key)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (GamepadButton, Key)> + '_ {
+    self.buttons.iter().copied()
+  }
+}
+
+// drives imgui's gamepad navigation from one raylib gamepad, see
+// `RaylibGui::gamepad`
+pub struct GamepadInput {
+  pub buttons: GamepadMap,
+  select: GamepadSelect,
+  // per `GamepadAxis`, including the triggers
+  dead_zones: [f32; 6],
+  // negative and positive direction of each stick axis
+  sticks: [(GamepadAxis, Key, Key); 4],
+  triggers: [(GamepadAxis, Key); 2],
+  // per pad and trigger, whether it ever reported a value below zero
+  signed_triggers: [[bool; 2]; MAX_GAMEPADS as usize],
+
+  active: Option<i32>,
+  // the value last sent for every key that isn't released
+  held: Vec<(Key, f32)>,
+}
+
+impl Default for GamepadInput {
+  fn default() -> GamepadInput {
+    GamepadInput {
+      buttons: GamepadMap::default(),
+      select: GamepadSelect::default(),
+      dead_zones: [DEFAULT_DEAD_ZONE; 6],
+      sticks: [
+        (
+          GamepadAxis::GAMEPAD_AXIS_LEFT_X,
+          Key::GamepadLStickLeft,
+          Key::GamepadLStickRight,
+        ),
+        (
+          GamepadAxis::GAMEPAD_AXIS_LEFT_Y,
+          Key::GamepadLStickUp,
+          Key::GamepadLStickDown,
+        ),
+        (
+          GamepadAxis::GAMEPAD_AXIS_RIGHT_X,
+          Key::GamepadRStickLeft,
+          Key::GamepadRStickRight,
+        ),
+        (
+          GamepadAxis::GAMEPAD_AXIS_RIGHT_Y,
+          Key::GamepadRStickUp,
+          Key::GamepadRStickDown,
+        ),
+      ],
+      triggers: [
+        (GamepadAxis::GAMEPAD_AXIS_LEFT_TRIGGER, Key::GamepadL2),
+        (GamepadAxis::GAMEPAD_AXIS_RIGHT_TRIGGER, Key::GamepadR2),
+      ],
+      signed_triggers: [[false; 2]; MAX_GAMEPADS as usize],
+      active: None,
+      held: Vec::new(),
+    }
+  }
+}
+
+impl GamepadInput {
+  pub fn select(&mut self, select: GamepadSelect) {
+    self.select = select;
+  }
+
+  pub fn selected(&self) -> GamepadSelect {
+    self.select
+  }
+
+  // the pad imgui currently follows, none while no pad is connected
+  pub fn active(&self) -> Option<i32> {
+    self.active
+  }
+
+  // replaces the button mapping, including custom changes made to it
+  pub fn set_layout(&mut self, layout: GamepadLayout) {
+    self.buttons = GamepadMap::layout(layout);
+  }
+
+  // stick and trigger values closer to rest than `dead_zone` are ignored
+  pub fn set_dead_zone(&mut self, axis: GamepadAxis, dead_zone: f32) {
+    self.dead_zones[axis as usize] = dead_zone;
+  }
+
+  pub fn set_dead_zones(&mut self, dead_zone: f32) {
+    self.dead_zones = [dead_zone; 6];
+  }
+
+  pub fn dead_zone(&self, axis: GamepadAxis) -> f32 {
+    self.dead_zones[axis as usize]
+  }
+
+  pub(crate) fn update(
+    &mut self,
+    input: &impl InputSource,
+    io: &mut Io,
+    nav: bool,
+  ) {
+    self.detect_signed_triggers(input);
+    let pad = if nav { self.pick(input) } else { None };
+    if pad != self.active {
+      // the old pad's keys would stay held, e.g. when it was unplugged
+      self.release(io);
+      self.active = pad;
+    }
+    let Some(pad) = pad else { return };
+
+    // every key is sent as an analog value, buttons are 0 or 1 and the
+    // triggers share their keys with the L2/R2 buttons
+    let mut values: Vec<(Key, f32)> = Vec::new();
+    let mut press =
+      |key: Key, value: f32| match values.iter_mut().find(|(k, _)| *k == key) {
+        Some(entry) => entry.1 = entry.1.max(value),
+        None => values.push((key, value)),
+      };
+    for (button, key) in self.buttons.iter() {
+      if input.is_gamepad_button_down(pad, button) {
+        press(key, 1.0);
+      }
+    }
+    for &(axis, neg_key, pos_key) in &self.sticks {
+      let value = input.gamepad_axis_movement(pad, axis);
+      if value < -self.dead_zones[axis as usize] {
+        press(neg_key, -value);
+      } else if value > self.dead_zones[axis as usize] {
+        press(pos_key, value);
+      }
+    }
+    for (trigger, &(axis, key)) in self.triggers.iter().enumerate() {
+      let value = self.trigger_value(input, pad, trigger);
+      if value > self.dead_zones[axis as usize] {
+        press(key, value);
+      }
+    }
+
+    for &(key, _) in &self.held {
+      if !values.iter().any(|(k, _)| *k == key) {
+        io.add_key_analog_event(key, false, 0.0);
+      }
+    }
+    for &(key, value) in &values {
+      if !self.held.contains(&(key, value)) {
+        io.add_key_analog_event(key, true, value);
+      }
+    }
+    self.held = values;
+  }
+
+  // lets go of every key the active pad holds down
+  pub(crate) fn release(&mut self, io: &mut Io) {
+    for (key, _) in self.held.drain(..) {
+      io.add_key_analog_event(key, false, 0.0);
+    }
+  }
+
+  fn pick(&self, input: &impl InputSource) -> Option<i32> {
+    match self.select {
+      GamepadSelect::Index(pad) => {
+        input.is_gamepad_available(pad).then_some(pad)
+      },
+      GamepadSelect::LastActive => {
+        let current =
+          self.active.filter(|&pad| input.is_gamepad_available(pad));
+        let touched = (0..MAX_GAMEPADS).find(|&pad| {
+          Some(pad) != current
+            && input.is_gamepad_available(pad)
+            && self.touched(input, pad)
+        });
+        // until a pad was used, the first connected one
+        touched.or(current).or_else(|| {
+          (0..MAX_GAMEPADS).find(|&pad| input.is_gamepad_available(pad))
+        })
+      },
+    }
+  }
+
+  fn touched(&self, input: &impl InputSource, pad: i32) -> bool {
+    self
+      .buttons
+      .iter()
+      .any(|(button, _)| input.is_gamepad_button_pressed(pad, button))
+      || self.sticks.iter().any(|&(axis, _, _)| {
+        input.gamepad_axis_movement(pad, axis).abs()
+          > self.dead_zones[axis as usize]
+      })
+      || self.triggers.iter().enumerate().any(|(trigger, &(axis, _))| {
+        self.trigger_value(input, pad, trigger) > self.dead_zones[axis as usize]
+      })
+  }
+
+  // raylib reports triggers from -1 at rest to 1 fully pulled, but one that
+  // isn't mapped or wasn't touched yet reads 0. a trigger only counts as
+  // going from -1 once it went below 0, until then 0 is its rest
+  fn detect_signed_triggers(&mut self, input: &impl InputSource) {
+    let pads = (0..MAX_GAMEPADS).zip(&mut self.signed_triggers);
+    for (pad, signed) in pads {
+      if !input.is_gamepad_available(pad) {
+        *signed = [false; 2];
+        continue;
+      }
+      for (signed, &(axis, _)) in signed.iter_mut().zip(&self.triggers) {
+        *signed |= input.gamepad_axis_movement(pad, axis) < 0.0;
+      }
+    }
+  }
+
+  // from 0 at rest to 1 fully pulled
+  fn trigger_value(
+    &self,
+    input: &impl InputSource,
+    pad: i32,
+    trigger: usize,
+  ) -> f32 {
+    let (axis, _) = self.triggers[trigger];
+    let value = input.gamepad_axis_movement(pad, axis);
+    let signed = usize::try_from(pad)
+      .ok()
+      .and_then(|pad| self.signed_triggers.get(pad))
+      .is_some_and(|signed| signed[trigger]);
+    if signed {
+      (value + 1.0) / 2.0
+    } else {
+      value.max(0.0)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{testing, GamepadFrame, InputFrame, ScriptedInput};
+
+  const A: GamepadButton = GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN;
+  const UP: GamepadButton = GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP;
+
+  // feeds one frame of connected pads at a time into a `GamepadInput`
+  #[derive(Default)]
+  struct Pads {
+    gamepad: GamepadInput,
+    input: ScriptedInput,
+  }
+
+  impl Pads {
+    fn step(&mut self, io: &mut Io, gamepads: Vec<GamepadFrame>) {
+      self.input.push(InputFrame { gamepads, ..InputFrame::default() });
+      self.input.advance();
+      self.gamepad.update(&self.input, io, true);
+    }
+
+    fn held(&self, key: Key) -> Option<f32> {
+      self.gamepad.held.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    }
+  }
+
+  fn buttons(buttons_down: &[GamepadButton]) -> GamepadFrame {
+    GamepadFrame { buttons_down: buttons_down.to_vec(), ..Default::default() }
+  }
+
+  fn axes(axes: &[(GamepadAxis, f32)]) -> GamepadFrame {
+    let mut pad = GamepadFrame::default();
+    for &(axis, value) in axes {
+      pad.axes[axis as usize] = value;
+    }
+    pad
+  }
+
+  #[test]
+  fn applies_dead_zones_per_axis() {
+    let mut ctx = testing::context([800.0, 600.0]);
+    let io = ctx.io_mut();
+    let mut pads = Pads::default();
+    pads.gamepad.set_dead_zone(GamepadAxis::GAMEPAD_AXIS_LEFT_X, 0.5);
+    assert_eq!(pads.gamepad.dead_zone(GamepadAxis::GAMEPAD_AXIS_LEFT_X), 0.5);
+    assert_eq!(pads.gamepad.dead_zone(GamepadAxis::GAMEPAD_AXIS_LEFT_Y), 0.2);
+
+    let stick = |x, y| {
+      vec![axes(&[
+        (GamepadAxis::GAMEPAD_AXIS_LEFT_X, x),
+        (GamepadAxis::GAMEPAD_AXIS_LEFT_Y, y),
+      ])]
+    };
+    pads.step(io, stick(0.4, -0.4));
+    assert_eq!(pads.held(Key::GamepadLStickRight), None);
+    assert_eq!(pads.held(Key::GamepadLStickUp), Some(0.4));
+
+    pads.step(io, stick(0.6, 0.1));
+    assert_eq!(pads.held(Key::GamepadLStickRight), Some(0.6));
+    assert_eq!(pads.held(Key::GamepadLStickUp), None);
+    assert_eq!(pads.held(Key::GamepadLStickDown), None);
+
+    pads.gamepad.set_dead_zones(0.0);
+    pads.step(io, stick(-0.1, 0.1));
+    assert_eq!(pads.held(Key::GamepadLStickLeft), Some(0.1));
+    assert_eq!(pads.held(Key::GamepadLStickDown), Some(0.1));
+  }
+
+  #[test]
+  fn triggers_rest_at_zero_until_they_go_negative() {
+    let mut ctx = testing::context([800.0, 600.0]);
+    let io = ctx.io_mut();
+    let mut pads = Pads::default();
+    let left = GamepadAxis::GAMEPAD_AXIS_LEFT_TRIGGER;
+    let right = GamepadAxis::GAMEPAD_AXIS_RIGHT_TRIGGER;
+
+    // unmapped triggers read 0 and don't make the second pad active
+    let unmapped = axes(&[(left, 0.0), (right, 0.0)]);
+    pads.step(io, vec![buttons(&[A]), unmapped.clone()]);
+    pads.step(io, vec![buttons(&[]), unmapped.clone()]);
+    assert_eq!(pads.gamepad.active(), Some(0));
+
+    pads.gamepad.select(GamepadSelect::Index(1));
+    pads.step(io, vec![buttons(&[]), unmapped]);
+    assert_eq!(pads.held(Key::GamepadL2), None);
+    assert_eq!(pads.held(Key::GamepadR2), None);
+
+    pads.step(io, vec![buttons(&[]), axes(&[(left, 0.5), (right, 0.0)])]);
+    assert_eq!(pads.held(Key::GamepadL2), Some(0.5));
+    assert_eq!(pads.held(Key::GamepadR2), None);
+
+    // a trigger that rests at -1 is half pulled at 0
+    pads.step(io, vec![buttons(&[]), axes(&[(left, -1.0), (right, -1.0)])]);
+    assert!(pads.gamepad.held.is_empty());
+    pads.step(io, vec![buttons(&[]), axes(&[(left, -1.0), (right, 0.0)])]);
+    assert_eq!(pads.held(Key::GamepadL2), None);
+    assert_eq!(pads.held(Key::GamepadR2), Some(0.5));
+  }
+
+  #[test]
+  fn selects_pads_by_index() {
+    let mut ctx = testing::context([800.0, 600.0]);
+    let io = ctx.io_mut();
+    let mut pads = Pads::default();
+    pads.gamepad.select(GamepadSelect::Index(1));
+
+    pads.step(io, vec![buttons(&[A]), buttons(&[])]);
+    assert_eq!(pads.gamepad.active(), Some(1));
+    assert_eq!(pads.held(Key::GamepadFaceDown), None);
+
+    pads.step(io, vec![buttons(&[]), buttons(&[A])]);
+    assert_eq!(pads.held(Key::GamepadFaceDown), Some(1.0));
+
+    // unplugged
+    pads.step(io, vec![buttons(&[])]);
+    assert_eq!(pads.gamepad.active(), None);
+    assert!(pads.gamepad.held.is_empty());
+  }
+
+  #[test]
+  fn follows_the_last_active_pad() {
+    let mut ctx = testing::context([800.0, 600.0]);
+    let io = ctx.io_mut();
+    let mut pads = Pads::default();
+
+    pads.step(io, vec![]);
+    assert_eq!(pads.gamepad.active(), None);
+
+    pads.step(io, vec![buttons(&[UP]), buttons(&[])]);
+    assert_eq!(pads.gamepad.active(), Some(0));
+    assert_eq!(pads.held(Key::GamepadDpadUp), Some(1.0));
+
+    // the old pad's keys are let go of when another one is used
+    pads.step(io, vec![buttons(&[UP]), buttons(&[A])]);
+    assert_eq!(pads.gamepad.active(), Some(1));
+    assert_eq!(pads.held(Key::GamepadDpadUp), None);
+    assert_eq!(pads.held(Key::GamepadFaceDown), Some(1.0));
+
+    // a stick past its dead zone counts as using the pad
+    let stick = axes(&[(GamepadAxis::GAMEPAD_AXIS_RIGHT_X, 0.9)]);
+    pads.step(io, vec![stick, buttons(&[A])]);
+    assert_eq!(pads.gamepad.active(), Some(0));
+    assert_eq!(pads.held(Key::GamepadRStickRight), Some(0.9));
+
+    pads.step(io, vec![]);
+    assert_eq!(pads.gamepad.active(), None);
+    assert!(pads.gamepad.held.is_empty());
+  }
+}
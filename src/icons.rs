@@ -0,0 +1,191 @@
+use crate::fonts::{FontData, StackSource};
+use imgui::{FontConfig, FontGlyphRanges};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+// private use area blocks the packs put their glyphs in
+const FONT_AWESOME_RANGES: &[u32] = &[0xe005, 0xf8ff, 0];
+const MATERIAL_RANGES: &[u32] = &[0xe000, 0xf8ff, 0];
+
+// an icon ttf/otf to merge into a font with `FontStack::merge_icons` or
+// `RaylibGui::merge_icons`. icons are drawn at `scale` times the size of
+// the font they are merged into and get a fixed advance so they line up
+// in menus and buttons
+#[derive(Clone)]
+pub struct IconFont {
+  data: FontData,
+  ranges: FontGlyphRanges,
+  scale: f32,
+  glyph_offset: [f32; 2],
+  monospace: bool,
+}
+
+impl IconFont {
+  // fa-solid-900.ttf and friends from font awesome 5 or 6
+  pub fn font_awesome(path: impl AsRef<Path>) -> IconFont {
+    IconFont::from_file(path, FONT_AWESOME_RANGES).scale(2.0 / 3.0)
+  }
+
+  // MaterialIcons-Regular.ttf from google's material icons
+  pub fn material(path: impl AsRef<Path>) -> IconFont {
+    IconFont::from_file(path, MATERIAL_RANGES)
+  }
+
+  // `ranges` are inclusive codepoint pairs ending in 0, like imgui expects
+  pub fn from_file(path: impl AsRef<Path>, ranges: &'static [u32]) -> IconFont {
+    IconFont::with(FontData::File(path.as_ref().into()), ranges)
+  }
+
+  pub fn from_memory(
+    data: impl Into<Vec<u8>>,
+    ranges: &'static [u32],
+  ) -> IconFont {
+    IconFont::with(FontData::Memory(data.into()), ranges)
+  }
+
+  pub fn scale(mut self, scale: f32) -> Self {
+    self.scale = scale;
+    self
+  }
+
+  // moves every icon, a few pixels down usually centers smaller icons on
+  // the text next to them
+  pub fn glyph_offset(mut self, offset: [f32; 2]) -> Self {
+    self.glyph_offset = offset;
+    self
+  }
+
+  pub fn monospace(mut self, monospace: bool) -> Self {
+    self.monospace = monospace;
+    self
+  }
+
+  fn with(data: FontData, ranges: &'static [u32]) -> IconFont {
+    IconFont {
+      data,
+      ranges: FontGlyphRanges::from_slice(ranges),
+      scale: 1.0,
+      glyph_offset: [0.0, 0.0],
+      monospace: true,
+    }
+  }
+
+  pub(crate) fn source(&self, base_size: f32) -> StackSource {
+    let size_pixels = base_size * self.scale;
+    StackSource {
+      data: self.data.clone(),
+      config: FontConfig {
+        size_pixels,
+        glyph_ranges: self.ranges.clone(),
+        glyph_offset: self.glyph_offset,
+        glyph_min_advance_x: if self.monospace { size_pixels } else { 0.0 },
+        pixel_snap_h: true,
+        ..FontConfig::default()
+      },
+    }
+  }
+}
+
+// icon names to codepoints, so `icons.label("save", "Save")` can be used
+// instead of pasting private use characters into strings
+#[derive(Clone, Default)]
+pub struct IconTable {
+  icons: HashMap<String, char>,
+}
+
+impl IconTable {
+  pub fn new() -> IconTable {
+    IconTable::default()
+  }
+
+  pub fn from_pairs<'a>(
+    pairs: impl IntoIterator<Item = (&'a str, u32)>,
+  ) -> IconTable {
+    let mut table = IconTable::new();
+    for (name, codepoint) in pairs {
+      table.insert(name, codepoint);
+    }
+    table
+  }
+
+  // "name codepoint" lines with the codepoint in hex, the format of the
+  // `codepoints` file shipped with material icons. lines that don't parse
+  // are skipped
+  pub fn parse(text: &str) -> IconTable {
+    let mut table = IconTable::new();
+    for line in text.lines() {
+      let mut parts = line.split_whitespace();
+      if let (Some(name), Some(codepoint), None) =
+        (parts.next(), parts.next(), parts.next())
+      {
+        let codepoint = codepoint.trim_start_matches("0x");
+        if let Ok(codepoint) = u32::from_str_radix(codepoint, 16) {
+          table.insert(name, codepoint);
+        }
+      }
+    }
+    table
+  }
+
+  pub fn load(path: impl AsRef<Path>) -> io::Result<IconTable> {
+    std::fs::read_to_string(path).map(|text| IconTable::parse(&text))
+  }
+
+  pub fn insert(&mut self, name: &str, codepoint: u32) {
+    if let Some(icon) = char::from_u32(codepoint) {
+      self.icons.insert(name.to_owned(), icon);
+    }
+  }
+
+  pub fn get(&self, name: &str) -> Option<char> {
+    self.icons.get(name).copied()
+  }
+
+  // the icon as a string, empty if the table doesn't know it
+  pub fn icon(&self, name: &str) -> String {
+    self.get(name).map(String::from).unwrap_or_default()
+  }
+
+  // "<icon> text", or just the text if the table doesn't know the icon
+  pub fn label(&self, name: &str, text: &str) -> String {
+    match self.get(name) {
+      Some(icon) => format!("{icon} {text}"),
+      None => text.to_owned(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_codepoints() {
+    let table =
+      IconTable::parse("save e161\nfolder 0xe2c7\r\n\n  home   e88a  \n");
+    assert_eq!(table.get("save"), Some('\u{e161}'));
+    assert_eq!(table.get("folder"), Some('\u{e2c7}'));
+    assert_eq!(table.get("home"), Some('\u{e88a}'));
+    assert_eq!(table.label("save", "Save"), "\u{e161} Save");
+    assert_eq!(table.label("missing", "Missing"), "Missing");
+  }
+
+  #[test]
+  fn skips_malformed_lines() {
+    let table = IconTable::parse(
+      "nocodepoint\nbad zzzz\nextra e161 e162\nsurrogate d800\nok e88a",
+    );
+    assert_eq!(table.get("nocodepoint"), None);
+    assert_eq!(table.get("bad"), None);
+    assert_eq!(table.get("extra"), None);
+    assert_eq!(table.get("surrogate"), None);
+    assert_eq!(table.icon("ok"), "\u{e88a}");
+  }
+
+  #[test]
+  fn later_duplicates_win() {
+    let table = IconTable::parse("save e161\nsave e162");
+    assert_eq!(table.get("save"), Some('\u{e162}'));
+  }
+}
@@ -0,0 +1,236 @@
+use crate::InputSource;
+use imgui::{sys, Context};
+use raylib::prelude::*;
+use std::ffi::CStr;
+
+// which input a window lets through to the game while imgui would
+// otherwise capture it, see `RaylibGui::set_passthrough`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Passthrough {
+  Mouse,
+  Keyboard,
+  All,
+}
+
+impl Passthrough {
+  fn mouse(self) -> bool {
+    self != Passthrough::Keyboard
+  }
+
+  fn keyboard(self) -> bool {
+    self != Passthrough::Mouse
+  }
+}
+
+// raylib's input queries for game code, answering "not pressed" for
+// whatever imgui captured this frame, `RaylibGui::input`
+pub struct GameInput<'a, I: InputSource = RaylibHandle> {
+  input: &'a I,
+  mouse_captured: bool,
+  keyboard_captured: bool,
+}
+
+impl<'a, I: InputSource> GameInput<'a, I> {
+  pub(crate) fn new(
+    input: &'a I,
+    ctx: &Context,
+    passthrough: &[(String, Passthrough)],
+  ) -> GameInput<'a, I> {
+    let io = ctx.io();
+    // imgui-rs allows one context at a time, so the current one is `ctx`
+    let (hovered, focused) = unsafe {
+      match sys::igGetCurrentContext().as_ref() {
+        Some(g) => (root_name(g.HoveredWindow), root_name(g.NavWindow)),
+        None => (None, None),
+      }
+    };
+    let passes = |window: Option<&CStr>, mouse: bool| {
+      window.is_some_and(|window| {
+        passthrough.iter().any(|(name, rule)| {
+          name.as_bytes() == window.to_bytes()
+            && if mouse { rule.mouse() } else { rule.keyboard() }
+        })
+      })
+    };
+    GameInput {
+      input,
+      mouse_captured: io.want_capture_mouse && !passes(hovered, true),
+      // a text field keeps the keyboard even in a passthrough window
+      keyboard_captured: io.want_text_input
+        || (io.want_capture_keyboard && !passes(focused, false)),
+    }
+  }
+
+  pub fn mouse_captured(&self) -> bool {
+    self.mouse_captured
+  }
+
+  pub fn keyboard_captured(&self) -> bool {
+    self.keyboard_captured
+  }
+
+  pub fn is_key_pressed(&self, key: KeyboardKey) -> bool {
+    !self.keyboard_captured && self.input.is_key_pressed(key as i32)
+  }
+
+  pub fn is_key_pressed_repeat(&self, key: KeyboardKey) -> bool {
+    !self.keyboard_captured && self.input.is_key_pressed_repeat(key as i32)
+  }
+
+  pub fn is_key_down(&self, key: KeyboardKey) -> bool {
+    !self.keyboard_captured && self.input.is_key_down(key as i32)
+  }
+
+  // releases always go through, a key the game saw go down shouldn't
+  // stay held because imgui took the keyboard in between
+  pub fn is_key_released(&self, key: KeyboardKey) -> bool {
+    self.input.is_key_released(key as i32)
+  }
+
+  pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+    !self.mouse_captured && self.input.is_mouse_button_pressed(button)
+  }
+
+  pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+    !self.mouse_captured && self.input.is_mouse_button_down(button)
+  }
+
+  pub fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+    self.input.is_mouse_button_released(button)
+  }
+
+  // the larger of the two axes, like raylib's `GetMouseWheelMove`
+  pub fn get_mouse_wheel_move(&self) -> f32 {
+    let wheel = self.get_mouse_wheel_move_v();
+    if wheel.x.abs() > wheel.y.abs() {
+      wheel.x
+    } else {
+      wheel.y
+    }
+  }
+
+  pub fn get_mouse_wheel_move_v(&self) -> Vector2 {
+    if self.mouse_captured {
+      Vector2::zero()
+    } else {
+      self.input.mouse_wheel_move()
+    }
+  }
+}
+
+impl GameInput<'_, RaylibHandle> {
+  // takes the next typed character off raylib's queue
+  pub fn get_char_pressed(&self) -> Option<char> {
+    if self.keyboard_captured {
+      return None;
+    }
+    char::from_u32(unsafe { ffi::GetCharPressed() } as u32)
+      .filter(|c| *c != '\0')
+  }
+}
+
+// rules match the name the top level window was created with
+unsafe fn root_name<'a>(window: *mut sys::ImGuiWindow) -> Option<&'a CStr> {
+  let window = window.as_ref()?.RootWindow.as_ref()?;
+  (!window.Name.is_null()).then(|| CStr::from_ptr(window.Name))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{testing, InputFrame, RaylibGui, ScriptedInput};
+  use imgui::{Condition, ConfigFlags};
+
+  const OVER_GAME: [f32; 2] = [100.0, 100.0];
+  const OVER_TOOLS: [f32; 2] = [400.0, 100.0];
+
+  // a "Game" window left of a "Tools" one, with the mouse at `mouse`, the
+  // keyboard focus in `focus` and a text field being typed into there if
+  // `typing`. w is held the whole time
+  fn run(
+    gui: &mut RaylibGui,
+    input: &mut ScriptedInput,
+    mouse: [f32; 2],
+    focus: &str,
+    typing: bool,
+  ) {
+    // a few frames, hover and focus follow the frame before
+    for _ in 0..3 {
+      input.push(InputFrame {
+        mouse_position: Some(Vector2::new(mouse[0], mouse[1])),
+        keys_down: vec![KeyboardKey::KEY_W as i32],
+        ..InputFrame::default()
+      });
+      input.advance();
+      let ui = gui.begin(input);
+      for (name, x) in [("Game", 0.0), ("Tools", 300.0)] {
+        ui.window(name)
+          .position([x, 0.0], Condition::Always)
+          .size([200.0, 200.0], Condition::Always)
+          .focused(name == focus)
+          .build(|| {
+            if typing && name == focus {
+              ui.set_keyboard_focus_here();
+              ui.input_text("##text", &mut String::new()).build();
+            }
+          });
+      }
+      gui.end();
+    }
+  }
+
+  fn captured(gui: &RaylibGui, input: &ScriptedInput) -> (bool, bool) {
+    let game = gui.input(input);
+    assert_eq!(game.is_key_down(KeyboardKey::KEY_W), !game.keyboard_captured());
+    (game.mouse_captured(), game.keyboard_captured())
+  }
+
+  #[test]
+  fn passes_input_through_named_windows() {
+    let _lock = testing::lock();
+    let mut input = ScriptedInput::default();
+    let mut gui = RaylibGui::headless(&input);
+    gui.context.io_mut().config_flags |= ConfigFlags::NAV_ENABLE_KEYBOARD;
+
+    run(&mut gui, &mut input, OVER_GAME, "Game", false);
+    assert_eq!(captured(&gui, &input), (true, true));
+
+    gui.set_passthrough("Game", Passthrough::Mouse);
+    run(&mut gui, &mut input, OVER_GAME, "Game", false);
+    assert_eq!(captured(&gui, &input), (false, true));
+
+    gui.set_passthrough("Game", Passthrough::Keyboard);
+    run(&mut gui, &mut input, OVER_GAME, "Game", false);
+    assert_eq!(captured(&gui, &input), (true, false));
+
+    // the rule follows the window under the mouse and the focused one
+    gui.set_passthrough("Game", Passthrough::All);
+    run(&mut gui, &mut input, OVER_GAME, "Game", false);
+    assert_eq!(captured(&gui, &input), (false, false));
+    run(&mut gui, &mut input, OVER_TOOLS, "Game", false);
+    assert_eq!(captured(&gui, &input), (true, false));
+    run(&mut gui, &mut input, OVER_GAME, "Tools", false);
+    assert_eq!(captured(&gui, &input), (false, true));
+
+    // a text field keeps the keyboard even in a passthrough window
+    run(&mut gui, &mut input, OVER_GAME, "Game", true);
+    assert_eq!(captured(&gui, &input), (false, true));
+
+    gui.remove_passthrough("Game");
+    run(&mut gui, &mut input, OVER_GAME, "Game", false);
+    assert_eq!(captured(&gui, &input), (true, true));
+  }
+
+  #[test]
+  fn passes_input_imgui_doesnt_want() {
+    let _lock = testing::lock();
+    let mut input = ScriptedInput::default();
+    let mut gui = RaylibGui::headless(&input);
+
+    // without keyboard navigation a focused window doesn't take the keys
+    run(&mut gui, &mut input, [700.0, 500.0], "Game", false);
+    assert_eq!(captured(&gui, &input), (false, false));
+    let io = gui.context.io();
+    assert!(!io.want_capture_mouse && !io.want_capture_keyboard);
+  }
+}
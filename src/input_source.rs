@@ -0,0 +1,388 @@
+use raylib::ffi;
+use raylib::prelude::*;
+use std::collections::VecDeque;
+
+// everything `RaylibGui` reads from the platform each frame. implemented
+// for `RaylibHandle`, and by `ScriptedInput` to drive a gui without a
+// window. keys are raylib key codes, like in `KeyMap`
+pub trait InputSource {
+  fn is_window_focused(&self) -> bool;
+  // the size imgui lays out in, the monitor's while fullscreen
+  fn screen_size(&self) -> [f32; 2];
+  fn is_window_highdpi(&self) -> bool;
+  fn window_scale_dpi(&self) -> [f32; 2];
+  fn frame_time(&self) -> f32;
+  fn fps(&self) -> u32;
+
+  fn is_key_pressed(&self, code: i32) -> bool;
+  fn is_key_pressed_repeat(&self, code: i32) -> bool;
+  fn is_key_released(&self, code: i32) -> bool;
+  fn is_key_down(&self, code: i32) -> bool;
+  // takes the next typed character off the queue
+  fn get_char_pressed(&mut self) -> Option<char>;
+
+  fn mouse_position(&self) -> Vector2;
+  fn is_cursor_on_screen(&self) -> bool;
+  fn is_mouse_button_pressed(&self, button: MouseButton) -> bool;
+  fn is_mouse_button_down(&self, button: MouseButton) -> bool;
+  fn is_mouse_button_released(&self, button: MouseButton) -> bool;
+  fn mouse_wheel_move(&self) -> Vector2;
+
+  fn touch_point_count(&self) -> u32;
+  fn touch_position(&self, index: u32) -> Vector2;
+
+  fn is_gamepad_available(&self, pad: i32) -> bool;
+  fn is_gamepad_button_pressed(&self, pad: i32, button: GamepadButton) -> bool;
+  fn is_gamepad_button_down(&self, pad: i32, button: GamepadButton) -> bool;
+  fn gamepad_axis_movement(&self, pad: i32, axis: GamepadAxis) -> f32;
+
+  // imgui asked to warp the mouse, `io.want_set_mouse_pos`
+  fn set_mouse_position(&mut self, position: Vector2);
+  // none hides the cursor
+  fn set_cursor(&mut self, cursor: Option<MouseCursor>);
+}
+
+impl InputSource for RaylibHandle {
+  fn is_window_focused(&self) -> bool {
+    RaylibHandle::is_window_focused(self)
+  }
+
+  fn screen_size(&self) -> [f32; 2] {
+    if self.is_window_fullscreen() {
+      let monitor = window::get_current_monitor();
+      [
+        window::get_monitor_width(monitor) as f32,
+        window::get_monitor_height(monitor) as f32,
+      ]
+    } else {
+      [self.get_screen_width() as f32, self.get_screen_height() as f32]
+    }
+  }
+
+  fn is_window_highdpi(&self) -> bool {
+    self.get_window_state().window_highdpi()
+  }
+
+  fn window_scale_dpi(&self) -> [f32; 2] {
+    let dpi = self.get_window_scale_dpi();
+    [dpi.x, dpi.y]
+  }
+
+  fn frame_time(&self) -> f32 {
+    self.get_frame_time()
+  }
+
+  fn fps(&self) -> u32 {
+    self.get_fps()
+  }
+
+  fn is_key_pressed(&self, code: i32) -> bool {
+    unsafe { ffi::IsKeyPressed(code) }
+  }
+
+  fn is_key_pressed_repeat(&self, code: i32) -> bool {
+    unsafe { ffi::IsKeyPressedRepeat(code) }
+  }
+
+  fn is_key_released(&self, code: i32) -> bool {
+    unsafe { ffi::IsKeyReleased(code) }
+  }
+
+  fn is_key_down(&self, code: i32) -> bool {
+    unsafe { ffi::IsKeyDown(code) }
+  }
+
+  fn get_char_pressed(&mut self) -> Option<char> {
+    RaylibHandle::get_char_pressed(self)
+  }
+
+  fn mouse_position(&self) -> Vector2 {
+    Vector2::new(self.get_mouse_x() as f32, self.get_mouse_y() as f32)
+  }
+
+  fn is_cursor_on_screen(&self) -> bool {
+    RaylibHandle::is_cursor_on_screen(self)
+  }
+
+  fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+    RaylibHandle::is_mouse_button_pressed(self, button)
+  }
+
+  fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+    RaylibHandle::is_mouse_button_down(self, button)
+  }
+
+  fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+    RaylibHandle::is_mouse_button_released(self, button)
+  }
+
+  fn mouse_wheel_move(&self) -> Vector2 {
+    self.get_mouse_wheel_move_v()
+  }
+
+  fn touch_point_count(&self) -> u32 {
+    self.get_touch_point_count()
+  }
+
+  fn touch_position(&self, index: u32) -> Vector2 {
+    self.get_touch_position(index)
+  }
+
+  fn is_gamepad_available(&self, pad: i32) -> bool {
+    RaylibHandle::is_gamepad_available(self, pad)
+  }
+
+  fn is_gamepad_button_pressed(&self, pad: i32, button: GamepadButton) -> bool {
+    RaylibHandle::is_gamepad_button_pressed(self, pad, button)
+  }
+
+  fn is_gamepad_button_down(&self, pad: i32, button: GamepadButton) -> bool {
+    RaylibHandle::is_gamepad_button_down(self, pad, button)
+  }
+
+  fn gamepad_axis_movement(&self, pad: i32, axis: GamepadAxis) -> f32 {
+    self.get_gamepad_axis_movement(pad, axis)
+  }
+
+  fn set_mouse_position(&mut self, position: Vector2) {
+    RaylibHandle::set_mouse_position(self, position);
+  }
+
+  fn set_cursor(&mut self, cursor: Option<MouseCursor>) {
+    match cursor {
+      Some(cursor) => {
+        self.show_cursor();
+        self.set_mouse_cursor(cursor);
+      },
+      None => self.hide_cursor(),
+    }
+  }
+}
+
+// the state of one gamepad in an `InputFrame`
+#[derive(Clone, Debug)]
+pub struct GamepadFrame {
+  pub buttons_down: Vec<GamepadButton>,
+  // indexed by `GamepadAxis`, the triggers rest at -1
+  pub axes: [f32; 6],
+}
+
+impl Default for GamepadFrame {
+  fn default() -> GamepadFrame {
+    GamepadFrame {
+      buttons_down: Vec::new(),
+      axes: [0.0, 0.0, 0.0, 0.0, -1.0, -1.0],
+    }
+  }
+}
+
+// what the platform looks like for one frame of a `ScriptedInput`.
+// presses and releases come from comparing it with the frame before
+#[derive(Clone, Debug)]
+pub struct InputFrame {
+  pub focused: bool,
+  pub screen_size: [f32; 2],
+  pub highdpi: bool,
+  pub dpi_scale: [f32; 2],
+  pub frame_time: f32,
+  pub keys_down: Vec<i32>,
+  pub chars: Vec<char>,
+  // none while the cursor is off the window
+  pub mouse_position: Option<Vector2>,
+  pub mouse_down: Vec<MouseButton>,
+  pub mouse_wheel: Vector2,
+  pub touches: Vec<Vector2>,
+  // connected pads by index
+  pub gamepads: Vec<GamepadFrame>,
+}
+
+impl Default for InputFrame {
+  fn default() -> InputFrame {
+    InputFrame {
+      focused: true,
+      screen_size: [800.0, 600.0],
+      highdpi: false,
+      dpi_scale: [1.0, 1.0],
+      frame_time: 1.0 / 60.0,
+      keys_down: Vec::new(),
+      chars: Vec::new(),
+      mouse_position: None,
+      mouse_down: Vec::new(),
+      mouse_wheel: Vector2::zero(),
+      touches: Vec::new(),
+      gamepads: Vec::new(),
+    }
+  }
+}
+
+// replays a list of `InputFrame`s, one per `advance`, so the backend can
+// run without a window or input devices
+#[derive(Default)]
+pub struct ScriptedInput {
+  frames: VecDeque<InputFrame>,
+  previous: InputFrame,
+  current: InputFrame,
+  chars: VecDeque<char>,
+  cursor: Option<MouseCursor>,
+}
+
+impl ScriptedInput {
+  pub fn new(frames: impl IntoIterator<Item = InputFrame>) -> ScriptedInput {
+    ScriptedInput { frames: frames.into_iter().collect(), ..Default::default() }
+  }
+
+  pub fn push(&mut self, frame: InputFrame) {
+    self.frames.push_back(frame);
+  }
+
+  // moves on to the next frame, call it before every gui frame. returns
+  // false once the script ran out and the last frame stays current
+  pub fn advance(&mut self) -> bool {
+    let Some(frame) = self.frames.pop_front() else {
+      self.previous = self.current.clone();
+      self.current.chars.clear();
+      self.current.mouse_wheel = Vector2::zero();
+      return false;
+    };
+    self.chars = frame.chars.iter().copied().collect();
+    self.previous = std::mem::replace(&mut self.current, frame);
+    true
+  }
+
+  pub fn frame(&self) -> &InputFrame {
+    &self.current
+  }
+
+  // the cursor the gui last asked for, none while hidden
+  pub fn cursor(&self) -> Option<MouseCursor> {
+    self.cursor
+  }
+
+  fn gamepad(&self, pad: i32, previous: bool) -> Option<&GamepadFrame> {
+    let frame = if previous { &self.previous } else { &self.current };
+    usize::try_from(pad).ok().and_then(|pad| frame.gamepads.get(pad))
+  }
+}
+
+impl InputSource for ScriptedInput {
+  fn is_window_focused(&self) -> bool {
+    self.current.focused
+  }
+
+  fn screen_size(&self) -> [f32; 2] {
+    self.current.screen_size
+  }
+
+  fn is_window_highdpi(&self) -> bool {
+    self.current.highdpi
+  }
+
+  fn window_scale_dpi(&self) -> [f32; 2] {
+    self.current.dpi_scale
+  }
+
+  fn frame_time(&self) -> f32 {
+    self.current.frame_time
+  }
+
+  fn fps(&self) -> u32 {
+    if self.current.frame_time > 0.0 {
+      (1.0 / self.current.frame_time).round() as u32
+    } else {
+      0
+    }
+  }
+
+  fn is_key_pressed(&self, code: i32) -> bool {
+    self.current.keys_down.contains(&code)
+      && !self.previous.keys_down.contains(&code)
+  }
+
+  fn is_key_released(&self, code: i32) -> bool {
+    !self.current.keys_down.contains(&code)
+      && self.previous.keys_down.contains(&code)
+  }
+
+  // scripts have no key repeat, every press is a new key down
+  fn is_key_pressed_repeat(&self, _code: i32) -> bool {
+    false
+  }
+
+  fn is_key_down(&self, code: i32) -> bool {
+    self.current.keys_down.contains(&code)
+  }
+
+  fn get_char_pressed(&mut self) -> Option<char> {
+    self.chars.pop_front()
+  }
+
+  fn mouse_position(&self) -> Vector2 {
+    self.current.mouse_position.unwrap_or_else(Vector2::zero)
+  }
+
+  fn is_cursor_on_screen(&self) -> bool {
+    self.current.mouse_position.is_some()
+  }
+
+  fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+    self.current.mouse_down.contains(&button)
+      && !self.previous.mouse_down.contains(&button)
+  }
+
+  fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+    self.current.mouse_down.contains(&button)
+  }
+
+  fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+    !self.current.mouse_down.contains(&button)
+      && self.previous.mouse_down.contains(&button)
+  }
+
+  fn mouse_wheel_move(&self) -> Vector2 {
+    self.current.mouse_wheel
+  }
+
+  fn touch_point_count(&self) -> u32 {
+    self.current.touches.len() as u32
+  }
+
+  fn touch_position(&self, index: u32) -> Vector2 {
+    self
+      .current
+      .touches
+      .get(index as usize)
+      .copied()
+      .unwrap_or_else(Vector2::zero)
+  }
+
+  fn is_gamepad_available(&self, pad: i32) -> bool {
+    self.gamepad(pad, false).is_some()
+  }
+
+  fn is_gamepad_button_pressed(&self, pad: i32, button: GamepadButton) -> bool {
+    let down = |previous| {
+      self
+        .gamepad(pad, previous)
+        .is_some_and(|pad| pad.buttons_down.contains(&button))
+    };
+    down(false) && !down(true)
+  }
+
+  fn is_gamepad_button_down(&self, pad: i32, button: GamepadButton) -> bool {
+    self
+      .gamepad(pad, false)
+      .is_some_and(|pad| pad.buttons_down.contains(&button))
+  }
+
+  fn gamepad_axis_movement(&self, pad: i32, axis: GamepadAxis) -> f32 {
+    self.gamepad(pad, false).map_or(0.0, |pad| pad.axes[axis as usize])
+  }
+
+  fn set_mouse_position(&mut self, position: Vector2) {
+    self.current.mouse_position = Some(position);
+  }
+
+  fn set_cursor(&mut self, cursor: Option<MouseCursor>) {
+    self.cursor = cursor;
+  }
+}
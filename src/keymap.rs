@@ -0,0 +1,233 @@
+use imgui::Key;
+use raylib::consts::KeyboardKey;
+
+// glfw key code of F13, raylib passes F13 to F24 through without naming
+// them in `KeyboardKey`
+const KEY_F13: i32 = 302;
+
+// which raylib key drives which imgui key, `RaylibGui::keys`. raylib keys
+// are plain key codes so keys without a `KeyboardKey` variant can be
+// mapped too. the volume keys have no imgui key and aren't mapped
+#[derive(Clone)]
+pub struct KeyMap {
+  keys: Vec<(i32, Key)>,
+}
+
+impl Default for KeyMap {
+  fn default() -> KeyMap {
+    KeyMap { keys: DEFAULT_KEYS.to_vec() }
+  }
+}
+
+impl KeyMap {
+  pub fn new() -> KeyMap {
+    KeyMap::default()
+  }
+
+  pub fn empty() -> KeyMap {
+    KeyMap { keys: Vec::new() }
+  }
+
+  pub fn set(&mut self, key: KeyboardKey, imgui_key: Key) {
+    self.set_code(key as i32, imgui_key);
+  }
+
+  // replaces what `code` was mapped to before
+  pub fn set_code(&mut self, code: i32, imgui_key: Key) {
+    match self.keys.iter_mut().find(|(c, _)| *c == code) {
+      Some(entry) => entry.1 = imgui_key,
+      None => self.keys.push((code, imgui_key)),
+    }
+  }
+
+  // imgui no longer sees the key, e.g. `KEY_ESCAPE` when it is raylib's
+  // exit key and shouldn't also close popups
+  pub fn remove(&mut self, key: KeyboardKey) {
+    self.remove_code(key as i32);
+  }
+
+  pub fn remove_code(&mut self, code: i32) {
+    self.keys.retain(|(c, _)| *c != code);
+  }
+
+  pub fn get(&self, key: KeyboardKey) -> Option<Key> {
+    self.get_code(key as i32)
+  }
+
+  pub fn get_code(&self, code: i32) -> Option<Key> {
+    self.keys.iter().find(|(c, _)| *c == code).map(|(_, key)| *key)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (i32, Key)> + '_ {
+    self.keys.iter().copied()
+  }
+}
+
+const DEFAULT_KEYS: &[(i32, Key)] = &[
+  (KeyboardKey::KEY_APOSTROPHE as i32, Key::Apostrophe),
+  (KeyboardKey::KEY_COMMA as i32, Key::Comma),
+  (KeyboardKey::KEY_MINUS as i32, Key::Minus),
+  (KeyboardKey::KEY_PERIOD as i32, Key::Period),
+  (KeyboardKey::KEY_SLASH as i32, Key::Slash),
+  (KeyboardKey::KEY_ZERO as i32, Key::Alpha0),
+  (KeyboardKey::KEY_ONE as i32, Key::Alpha1),
+  (KeyboardKey::KEY_TWO as i32, Key::Alpha2),
+  (KeyboardKey::KEY_THREE as i32, Key::Alpha3),
+  (KeyboardKey::KEY_FOUR as i32, Key::Alpha4),
+  (KeyboardKey::KEY_FIVE as i32, Key::Alpha5),
+  (KeyboardKey::KEY_SIX as i32, Key::Alpha6),
+  (KeyboardKey::KEY_SEVEN as i32, Key::Alpha7),
+  (KeyboardKey::KEY_EIGHT as i32, Key::Alpha8),
+  (KeyboardKey::KEY_NINE as i32, Key::Alpha9),
+  (KeyboardKey::KEY_SEMICOLON as i32, Key::Semicolon),
+  (KeyboardKey::KEY_EQUAL as i32, Key::Equal),
+  (KeyboardKey::KEY_A as i32, Key::A),
+  (KeyboardKey::KEY_B as i32, Key::B),
+  (KeyboardKey::KEY_C as i32, Key::C),
+  (KeyboardKey::KEY_D as i32, Key::D),
+  (KeyboardKey::KEY_E as i32, Key::E),
+  (KeyboardKey::KEY_F as i32, Key::F),
+  (KeyboardKey::KEY_G as i32, Key::G),
+  (KeyboardKey::KEY_H as i32, Key::H),
+  (KeyboardKey::KEY_I as i32, Key::I),
+  (KeyboardKey::KEY_J as i32, Key::J),
+  (KeyboardKey::KEY_K as i32, Key::K),
+  (KeyboardKey::KEY_L as i32, Key::L),
+  (KeyboardKey::KEY_M as i32, Key::M),
+  (KeyboardKey::KEY_N as i32, Key::N),
+  (KeyboardKey::KEY_O as i32, Key::O),
+  (KeyboardKey::KEY_P as i32, Key::P),
+  (KeyboardKey::KEY_Q as i32, Key::Q),
+  (KeyboardKey::KEY_R as i32, Key::R),
+  (KeyboardKey::KEY_S as i32, Key::S),
+  (KeyboardKey::KEY_T as i32, Key::T),
+  (KeyboardKey::KEY_U as i32, Key::U),
+  (KeyboardKey::KEY_V as i32, Key::V),
+  (KeyboardKey::KEY_W as i32, Key::W),
+  (KeyboardKey::KEY_X as i32, Key::X),
+  (KeyboardKey::KEY_Y as i32, Key::Y),
+  (KeyboardKey::KEY_Z as i32, Key::Z),
+  (KeyboardKey::KEY_SPACE as i32, Key::Space),
+  (KeyboardKey::KEY_ESCAPE as i32, Key::Escape),
+  (KeyboardKey::KEY_ENTER as i32, Key::Enter),
+  (KeyboardKey::KEY_TAB as i32, Key::Tab),
+  (KeyboardKey::KEY_BACKSPACE as i32, Key::Backspace),
+  (KeyboardKey::KEY_INSERT as i32, Key::Insert),
+  (KeyboardKey::KEY_DELETE as i32, Key::Delete),
+  (KeyboardKey::KEY_RIGHT as i32, Key::RightArrow),
+  (KeyboardKey::KEY_LEFT as i32, Key::LeftArrow),
+  (KeyboardKey::KEY_DOWN as i32, Key::DownArrow),
+  (KeyboardKey::KEY_UP as i32, Key::UpArrow),
+  (KeyboardKey::KEY_PAGE_UP as i32, Key::PageUp),
+  (KeyboardKey::KEY_PAGE_DOWN as i32, Key::PageDown),
+  (KeyboardKey::KEY_HOME as i32, Key::Home),
+  (KeyboardKey::KEY_END as i32, Key::End),
+  (KeyboardKey::KEY_CAPS_LOCK as i32, Key::CapsLock),
+  (KeyboardKey::KEY_SCROLL_LOCK as i32, Key::ScrollLock),
+  (KeyboardKey::KEY_NUM_LOCK as i32, Key::NumLock),
+  (KeyboardKey::KEY_PRINT_SCREEN as i32, Key::PrintScreen),
+  (KeyboardKey::KEY_PAUSE as i32, Key::Pause),
+  (KeyboardKey::KEY_F1 as i32, Key::F1),
+  (KeyboardKey::KEY_F2 as i32, Key::F2),
+  (KeyboardKey::KEY_F3 as i32, Key::F3),
+  (KeyboardKey::KEY_F4 as i32, Key::F4),
+  (KeyboardKey::KEY_F5 as i32, Key::F5),
+  (KeyboardKey::KEY_F6 as i32, Key::F6),
+  (KeyboardKey::KEY_F7 as i32, Key::F7),
+  (KeyboardKey::KEY_F8 as i32, Key::F8),
+  (KeyboardKey::KEY_F9 as i32, Key::F9),
+  (KeyboardKey::KEY_F10 as i32, Key::F10),
+  (KeyboardKey::KEY_F11 as i32, Key::F11),
+  (KeyboardKey::KEY_F12 as i32, Key::F12),
+  (KEY_F13, Key::F13),
+  (KEY_F13 + 1, Key::F14),
+  (KEY_F13 + 2, Key::F15),
+  (KEY_F13 + 3, Key::F16),
+  (KEY_F13 + 4, Key::F17),
+  (KEY_F13 + 5, Key::F18),
+  (KEY_F13 + 6, Key::F19),
+  (KEY_F13 + 7, Key::F20),
+  (KEY_F13 + 8, Key::F21),
+  (KEY_F13 + 9, Key::F22),
+  (KEY_F13 + 10, Key::F23),
+  (KEY_F13 + 11, Key::F24),
+  (KeyboardKey::KEY_LEFT_SHIFT as i32, Key::LeftShift),
+  (KeyboardKey::KEY_LEFT_CONTROL as i32, Key::LeftCtrl),
+  (KeyboardKey::KEY_LEFT_ALT as i32, Key::LeftAlt),
+  (KeyboardKey::KEY_LEFT_SUPER as i32, Key::LeftSuper),
+  (KeyboardKey::KEY_RIGHT_SHIFT as i32, Key::RightShift),
+  (KeyboardKey::KEY_RIGHT_CONTROL as i32, Key::RightCtrl),
+  (KeyboardKey::KEY_RIGHT_ALT as i32, Key::RightAlt),
+  (KeyboardKey::KEY_RIGHT_SUPER as i32, Key::RightSuper),
+  (KeyboardKey::KEY_KB_MENU as i32, Key::Menu),
+  (KeyboardKey::KEY_MENU as i32, Key::Menu),
+  (KeyboardKey::KEY_BACK as i32, Key::AppBack),
+  (KeyboardKey::KEY_LEFT_BRACKET as i32, Key::LeftBracket),
+  (KeyboardKey::KEY_BACKSLASH as i32, Key::Backslash),
+  (KeyboardKey::KEY_RIGHT_BRACKET as i32, Key::RightBracket),
+  (KeyboardKey::KEY_GRAVE as i32, Key::GraveAccent),
+  (KeyboardKey::KEY_KP_0 as i32, Key::Keypad0),
+  (KeyboardKey::KEY_KP_1 as i32, Key::Keypad1),
+  (KeyboardKey::KEY_KP_2 as i32, Key::Keypad2),
+  (KeyboardKey::KEY_KP_3 as i32, Key::Keypad3),
+  (KeyboardKey::KEY_KP_4 as i32, Key::Keypad4),
+  (KeyboardKey::KEY_KP_5 as i32, Key::Keypad5),
+  (KeyboardKey::KEY_KP_6 as i32, Key::Keypad6),
+  (KeyboardKey::KEY_KP_7 as i32, Key::Keypad7),
+  (KeyboardKey::KEY_KP_8 as i32, Key::Keypad8),
+  (KeyboardKey::KEY_KP_9 as i32, Key::Keypad9),
+  (KeyboardKey::KEY_KP_DECIMAL as i32, Key::KeypadDecimal),
+  (KeyboardKey::KEY_KP_DIVIDE as i32, Key::KeypadDivide),
+  (KeyboardKey::KEY_KP_MULTIPLY as i32, Key::KeypadMultiply),
+  (KeyboardKey::KEY_KP_SUBTRACT as i32, Key::KeypadSubtract),
+  (KeyboardKey::KEY_KP_ADD as i32, Key::KeypadAdd),
+  (KeyboardKey::KEY_KP_ENTER as i32, Key::KeypadEnter),
+  (KeyboardKey::KEY_KP_EQUAL as i32, Key::KeypadEqual),
+];
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn default_keys_are_unique() {
+    for (i, (code, _)) in DEFAULT_KEYS.iter().enumerate() {
+      let later = DEFAULT_KEYS[i + 1..].iter().any(|(c, _)| c == code);
+      assert!(!later, "key code {code} is mapped twice");
+    }
+  }
+
+  #[test]
+  fn looks_up_default_keys() {
+    let keys = KeyMap::new();
+    assert_eq!(keys.get(KeyboardKey::KEY_A), Some(Key::A));
+    assert_eq!(keys.get(KeyboardKey::KEY_ENTER), Some(Key::Enter));
+    assert_eq!(keys.get(KeyboardKey::KEY_KP_ENTER), Some(Key::KeypadEnter));
+    assert_eq!(keys.get(KeyboardKey::KEY_RIGHT_SUPER), Some(Key::RightSuper));
+    assert_eq!(keys.get_code(KEY_F13), Some(Key::F13));
+    assert_eq!(keys.get_code(KEY_F13 + 11), Some(Key::F24));
+    assert_eq!(keys.get(KeyboardKey::KEY_VOLUME_UP), None);
+  }
+
+  #[test]
+  fn overrides_keys() {
+    let mut keys = KeyMap::new();
+    let len = keys.iter().count();
+
+    keys.set(KeyboardKey::KEY_A, Key::B);
+    assert_eq!(keys.get(KeyboardKey::KEY_A), Some(Key::B));
+    assert_eq!(keys.iter().count(), len);
+
+    keys.set_code(400, Key::F1);
+    assert_eq!(keys.get_code(400), Some(Key::F1));
+    assert_eq!(keys.iter().count(), len + 1);
+
+    keys.remove(KeyboardKey::KEY_ESCAPE);
+    assert_eq!(keys.get(KeyboardKey::KEY_ESCAPE), None);
+    keys.remove_code(400);
+    assert_eq!(keys.get_code(400), None);
+    assert_eq!(keys.iter().count(), len - 1);
+
+    assert_eq!(KeyMap::empty().iter().count(), 0);
+  }
+}
@@ -1,16 +1,78 @@
 use ffi;
 use imgui::{
-  self, internal::RawWrapper, BackendFlags, ClipboardBackend, ConfigFlags,
-  Context, DrawCmd, DrawCmdParams, DrawVert, FontConfig, Key, Ui,
+  self,
+  internal::{RawCast, RawWrapper},
+  sys, BackendFlags, ClipboardBackend, ConfigFlags, Context, DrawCmd,
+  DrawCmdParams, DrawData, DrawVert, Key, Ui,
 };
 use raylib::ffi::{GetClipboardText, SetClipboardText};
 use raylib::prelude::*;
 use std::ffi::{c_char, CStr, CString};
 use std::fs::{self, File};
 use std::io::Write;
+use std::time::Instant;
+
+mod buffered;
+mod callback;
+mod capture;
+mod error;
+mod fonts;
+mod gamepad;
+mod icons;
+mod input;
+mod input_source;
+mod keymap;
+mod materials;
+mod raylib_font;
+mod software;
+mod stats;
+#[cfg(test)]
+mod testing;
+mod textures;
+mod touch;
+mod widgets;
+
+pub use buffered::BufferedRenderer;
+pub use callback::RaylibCallbackContext;
+pub use capture::{
+  CapturedCmd, CapturedDrawList, CapturedParams, CapturedTexture, FrameCapture,
+};
+pub use error::{Error, Result};
+pub use fonts::{FontId, FontStack};
+pub use gamepad::{GamepadInput, GamepadLayout, GamepadMap, GamepadSelect};
+pub use icons::{IconFont, IconTable};
+pub use input::{GameInput, Passthrough};
+pub use input_source::{GamepadFrame, InputFrame, InputSource, ScriptedInput};
+pub use keymap::KeyMap;
+pub use materials::{TextureOverride, UiBlend};
+pub use software::{SoftwareImage, SoftwareRenderer};
+pub use stats::FrameStats;
+pub use textures::{TextureHandle, TextureRegistry};
+pub use touch::TouchInput;
+pub use widgets::RaylibUiExt;
+
+use callback::Callbacks;
+use error::ErrorHook;
+use fonts::Fonts;
+use materials::{Material, Materials};
+use stats::Stats;
 
 pub struct RaylibGui {
   pub context: Context,
+  pub textures: TextureRegistry,
+  pub keys: KeyMap,
+  pub gamepad: GamepadInput,
+  pub touch: TouchInput,
+  materials: Materials,
+  fonts: Fonts,
+  stats: Stats,
+  error_hook: ErrorHook,
+  passthrough: Vec<(String, Passthrough)>,
+
+  ui_scale: f32,
+  auto_ui_scale: bool,
+  // style sizes at a ui scale of 1
+  base_style: Option<imgui::Style>,
 
   current_mouse_cursor: Option<imgui::MouseCursor>,
 
@@ -20,9 +82,18 @@ pub struct RaylibGui {
   last_alt_pressed: bool,
   last_super_pressed: bool,
 
-  keys: [(KeyboardKey, Key); 105],
-  gamepad_map: [(GamepadButton, Key); 16],
-  gamepad_axis: [(GamepadAxis, Key, Key); 4],
+  buffered: Option<BufferedRenderer>,
+  // boxed so the pointer the context keeps to it survives moves
+  callbacks: Box<Callbacks>,
+  // no window or gpu, see `RaylibGui::headless`
+  headless: bool,
+}
+
+pub enum RenderMode {
+  // push every vertex through rlgl's batch, works everywhere
+  Immediate,
+  // upload draw lists into vertex arrays, needs vao/vbo support
+  Buffered,
 }
 
 pub enum Style {
@@ -37,6 +108,9 @@ impl ClipboardBackend for RaylibClipboardBackend {
   fn get(&mut self) -> Option<String> {
     unsafe {
       let c = GetClipboardText();
+      if c.is_null() {
+        return None;
+      }
       let c = CStr::from_ptr(c as *mut c_char);
       c.to_str().map(|s| s.to_owned()).ok()
     }
@@ -54,6 +128,9 @@ impl ClipboardBackend for RaylibClipboardBackend {
 
 impl Drop for RaylibGui {
   fn drop(&mut self) {
+    if self.headless {
+      return;
+    }
     unsafe {
       let fonts = self.context.fonts();
 
@@ -62,7 +139,9 @@ impl Drop for RaylibGui {
         fonts.tex_id = 0.into();
       }
 
-      save_ini_settings(&mut self.context);
+      if let Err(error) = save_ini_settings(&mut self.context) {
+        (self.error_hook)(&error);
+      }
     }
   }
 }
@@ -73,7 +152,7 @@ pub struct Gui<'a> {
 
 impl<'a> Gui<'a> {
   pub fn begin(&mut self) -> &mut Ui {
-    self.gui.context.new_frame()
+    self.gui.new_frame()
   }
 }
 
@@ -91,184 +170,114 @@ pub trait RaylibDrawImGui: RaylibDraw {
 }
 
 impl RaylibGui {
-  pub fn new(rl: &mut RaylibHandle, _thread: &RaylibThread) -> RaylibGui {
+  // panics where `try_new` returns an error
+  pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> RaylibGui {
+    Self::with_render_mode(rl, thread, RenderMode::Immediate)
+  }
+
+  pub fn try_new(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+  ) -> Result<RaylibGui> {
+    Self::try_with_render_mode(rl, thread, RenderMode::Immediate)
+  }
+
+  pub fn with_render_mode(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    mode: RenderMode,
+  ) -> RaylibGui {
+    match Self::try_with_render_mode(rl, thread, mode) {
+      Ok(gui) => gui,
+      Err(error) => panic!("{error}"),
+    }
+  }
+
+  pub fn try_with_render_mode(
+    rl: &mut RaylibHandle,
+    _thread: &RaylibThread,
+    mode: RenderMode,
+  ) -> Result<RaylibGui> {
     let mut ctx = Context::create();
+    ctx.set_clipboard_backend(RaylibClipboardBackend);
+    load_ini_settings(&mut ctx);
+    RaylibGui::with_context(ctx, rl.is_window_focused(), mode, false)
+  }
+
+  // a gui that only reads `input` and never touches raylib's window or the
+  // gpu, e.g. to test ui code with `ScriptedInput`. the font atlas is built
+  // on the cpu only, frames are drawn with `SoftwareRenderer` or recorded
+  // with `end_captured`, `end` just finishes them. no ini file is used
+  pub fn headless(input: &impl InputSource) -> RaylibGui {
+    match Self::try_headless(input) {
+      Ok(gui) => gui,
+      Err(error) => panic!("{error}"),
+    }
+  }
+
+  pub fn try_headless(input: &impl InputSource) -> Result<RaylibGui> {
+    let mut ctx = Context::create();
+    ctx.set_ini_filename(None::<std::path::PathBuf>);
+    let focused = input.is_window_focused();
+    RaylibGui::with_context(ctx, focused, RenderMode::Immediate, true)
+  }
+
+  fn with_context(
+    mut ctx: Context,
+    focused: bool,
+    mode: RenderMode,
+    headless: bool,
+  ) -> Result<RaylibGui> {
     ctx.set_platform_name(Some(String::from("imgui_raylib_platform")));
     ctx.set_renderer_name(Some(String::from("imgui_raylib_renderer")));
     ctx.style_mut().use_dark_colors();
-    ctx.set_clipboard_backend(RaylibClipboardBackend);
-    ctx.fonts().add_font(&[imgui::FontSource::DefaultFontData {
-      config: Some(FontConfig { ..Default::default() }),
-    }]);
-
-    load_ini_settings(&mut ctx);
 
     let io = ctx.io_mut();
     io.backend_flags.insert(BackendFlags::HAS_GAMEPAD);
     io.backend_flags.insert(BackendFlags::HAS_MOUSE_CURSORS);
     io.backend_flags.insert(BackendFlags::HAS_SET_MOUSE_POS);
+    io.backend_flags.insert(BackendFlags::RENDERER_HAS_VTX_OFFSET);
     io.mouse_pos = [0.0, 0.0];
 
     let mut gui = RaylibGui {
       context: ctx,
+      textures: TextureRegistry::default(),
+      materials: Materials::default(),
+      fonts: Fonts::default(),
+      stats: Stats::default(),
+      error_hook: Box::new(error::log_error),
+      passthrough: Vec::new(),
+      ui_scale: 1.0,
+      auto_ui_scale: false,
+      base_style: None,
       current_mouse_cursor: None,
-      last_frame_focused: rl.is_window_focused(),
+      last_frame_focused: focused,
       last_control_pressed: false,
       last_shift_pressed: false,
       last_alt_pressed: false,
       last_super_pressed: false,
-      keys: [
-        // build up a map of raylib keys to ImGuiKeys
-        (KeyboardKey::KEY_APOSTROPHE, Key::Apostrophe),
-        (KeyboardKey::KEY_COMMA, Key::Comma),
-        (KeyboardKey::KEY_MINUS, Key::Minus),
-        (KeyboardKey::KEY_PERIOD, Key::Period),
-        (KeyboardKey::KEY_SLASH, Key::Slash),
-        (KeyboardKey::KEY_ZERO, Key::Keypad0),
-        (KeyboardKey::KEY_ONE, Key::Keypad1),
-        (KeyboardKey::KEY_TWO, Key::Keypad2),
-        (KeyboardKey::KEY_THREE, Key::Keypad3),
-        (KeyboardKey::KEY_FOUR, Key::Keypad4),
-        (KeyboardKey::KEY_FIVE, Key::Keypad5),
-        (KeyboardKey::KEY_SIX, Key::Keypad6),
-        (KeyboardKey::KEY_SEVEN, Key::Keypad7),
-        (KeyboardKey::KEY_EIGHT, Key::Keypad8),
-        (KeyboardKey::KEY_NINE, Key::Keypad9),
-        (KeyboardKey::KEY_SEMICOLON, Key::Semicolon),
-        (KeyboardKey::KEY_EQUAL, Key::Equal),
-        (KeyboardKey::KEY_A, Key::A),
-        (KeyboardKey::KEY_B, Key::B),
-        (KeyboardKey::KEY_C, Key::C),
-        (KeyboardKey::KEY_D, Key::D),
-        (KeyboardKey::KEY_E, Key::E),
-        (KeyboardKey::KEY_F, Key::F),
-        (KeyboardKey::KEY_G, Key::G),
-        (KeyboardKey::KEY_H, Key::H),
-        (KeyboardKey::KEY_I, Key::I),
-        (KeyboardKey::KEY_J, Key::J),
-        (KeyboardKey::KEY_K, Key::K),
-        (KeyboardKey::KEY_L, Key::L),
-        (KeyboardKey::KEY_M, Key::M),
-        (KeyboardKey::KEY_N, Key::N),
-        (KeyboardKey::KEY_O, Key::O),
-        (KeyboardKey::KEY_P, Key::P),
-        (KeyboardKey::KEY_Q, Key::Q),
-        (KeyboardKey::KEY_R, Key::R),
-        (KeyboardKey::KEY_S, Key::S),
-        (KeyboardKey::KEY_T, Key::T),
-        (KeyboardKey::KEY_U, Key::U),
-        (KeyboardKey::KEY_V, Key::V),
-        (KeyboardKey::KEY_W, Key::W),
-        (KeyboardKey::KEY_X, Key::X),
-        (KeyboardKey::KEY_Y, Key::Y),
-        (KeyboardKey::KEY_Z, Key::Z),
-        (KeyboardKey::KEY_SPACE, Key::Space),
-        (KeyboardKey::KEY_ESCAPE, Key::Escape),
-        (KeyboardKey::KEY_ENTER, Key::Enter),
-        (KeyboardKey::KEY_TAB, Key::Tab),
-        (KeyboardKey::KEY_BACKSPACE, Key::Backspace),
-        (KeyboardKey::KEY_INSERT, Key::Insert),
-        (KeyboardKey::KEY_DELETE, Key::Delete),
-        (KeyboardKey::KEY_RIGHT, Key::RightArrow),
-        (KeyboardKey::KEY_LEFT, Key::LeftArrow),
-        (KeyboardKey::KEY_DOWN, Key::DownArrow),
-        (KeyboardKey::KEY_UP, Key::UpArrow),
-        (KeyboardKey::KEY_PAGE_UP, Key::PageUp),
-        (KeyboardKey::KEY_PAGE_DOWN, Key::PageDown),
-        (KeyboardKey::KEY_HOME, Key::Home),
-        (KeyboardKey::KEY_END, Key::End),
-        (KeyboardKey::KEY_CAPS_LOCK, Key::CapsLock),
-        (KeyboardKey::KEY_SCROLL_LOCK, Key::ScrollLock),
-        (KeyboardKey::KEY_NUM_LOCK, Key::NumLock),
-        (KeyboardKey::KEY_PRINT_SCREEN, Key::PrintScreen),
-        (KeyboardKey::KEY_PAUSE, Key::Pause),
-        (KeyboardKey::KEY_F1, Key::F1),
-        (KeyboardKey::KEY_F2, Key::F2),
-        (KeyboardKey::KEY_F3, Key::F3),
-        (KeyboardKey::KEY_F4, Key::F4),
-        (KeyboardKey::KEY_F5, Key::F5),
-        (KeyboardKey::KEY_F6, Key::F6),
-        (KeyboardKey::KEY_F7, Key::F7),
-        (KeyboardKey::KEY_F8, Key::F8),
-        (KeyboardKey::KEY_F9, Key::F9),
-        (KeyboardKey::KEY_F10, Key::F10),
-        (KeyboardKey::KEY_F11, Key::F11),
-        (KeyboardKey::KEY_F12, Key::F12),
-        (KeyboardKey::KEY_LEFT_SHIFT, Key::LeftShift),
-        (KeyboardKey::KEY_LEFT_CONTROL, Key::LeftCtrl),
-        (KeyboardKey::KEY_LEFT_ALT, Key::LeftAlt),
-        (KeyboardKey::KEY_LEFT_SUPER, Key::LeftSuper),
-        (KeyboardKey::KEY_RIGHT_SHIFT, Key::RightShift),
-        (KeyboardKey::KEY_RIGHT_CONTROL, Key::RightCtrl),
-        (KeyboardKey::KEY_RIGHT_ALT, Key::RightAlt),
-        (KeyboardKey::KEY_RIGHT_SUPER, Key::RightSuper),
-        (KeyboardKey::KEY_KB_MENU, Key::Menu),
-        (KeyboardKey::KEY_LEFT_BRACKET, Key::LeftBracket),
-        (KeyboardKey::KEY_BACKSLASH, Key::Backslash),
-        (KeyboardKey::KEY_RIGHT_BRACKET, Key::RightBracket),
-        (KeyboardKey::KEY_GRAVE, Key::GraveAccent),
-        (KeyboardKey::KEY_KP_0, Key::Keypad0),
-        (KeyboardKey::KEY_KP_1, Key::Keypad1),
-        (KeyboardKey::KEY_KP_2, Key::Keypad2),
-        (KeyboardKey::KEY_KP_3, Key::Keypad3),
-        (KeyboardKey::KEY_KP_4, Key::Keypad4),
-        (KeyboardKey::KEY_KP_5, Key::Keypad5),
-        (KeyboardKey::KEY_KP_6, Key::Keypad6),
-        (KeyboardKey::KEY_KP_7, Key::Keypad7),
-        (KeyboardKey::KEY_KP_8, Key::Keypad8),
-        (KeyboardKey::KEY_KP_9, Key::Keypad9),
-        (KeyboardKey::KEY_KP_DECIMAL, Key::KeypadDecimal),
-        (KeyboardKey::KEY_KP_DIVIDE, Key::KeypadDivide),
-        (KeyboardKey::KEY_KP_MULTIPLY, Key::KeypadMultiply),
-        (KeyboardKey::KEY_KP_SUBTRACT, Key::KeypadSubtract),
-        (KeyboardKey::KEY_KP_ADD, Key::KeypadAdd),
-        (KeyboardKey::KEY_KP_ENTER, Key::KeypadEnter),
-        (KeyboardKey::KEY_KP_EQUAL, Key::KeypadEqual),
-      ],
-      gamepad_map: [
-        (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP, Key::GamepadDpadUp),
-        (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT, Key::GamepadDpadRight),
-        (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN, Key::GamepadDpadDown),
-        (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT, Key::GamepadDpadLeft),
-        (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_UP, Key::GamepadFaceUp),
-        (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT, Key::GamepadFaceLeft),
-        (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN, Key::GamepadFaceDown),
-        (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT, Key::GamepadFaceRight),
-        (GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_1, Key::GamepadL1),
-        (GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_2, Key::GamepadL2),
-        (GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_1, Key::GamepadR1),
-        (GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_2, Key::GamepadR2),
-        (GamepadButton::GAMEPAD_BUTTON_LEFT_THUMB, Key::GamepadL3),
-        (GamepadButton::GAMEPAD_BUTTON_RIGHT_THUMB, Key::GamepadR3),
-        (GamepadButton::GAMEPAD_BUTTON_MIDDLE_LEFT, Key::GamepadStart),
-        (GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT, Key::GamepadBack),
-      ],
-      gamepad_axis: [
-        (
-          GamepadAxis::GAMEPAD_AXIS_LEFT_X,
-          Key::GamepadLStickLeft,
-          Key::GamepadLStickRight,
-        ),
-        (
-          GamepadAxis::GAMEPAD_AXIS_LEFT_Y,
-          Key::GamepadLStickUp,
-          Key::GamepadLStickDown,
-        ),
-        (
-          GamepadAxis::GAMEPAD_AXIS_RIGHT_X,
-          Key::GamepadRStickLeft,
-          Key::GamepadRStickRight,
-        ),
-        (
-          GamepadAxis::GAMEPAD_AXIS_RIGHT_Y,
-          Key::GamepadRStickUp,
-          Key::GamepadRStickDown,
-        ),
-      ],
+      keys: KeyMap::default(),
+      gamepad: GamepadInput::default(),
+      touch: TouchInput::default(),
+      buffered: match mode {
+        RenderMode::Immediate => None,
+        RenderMode::Buffered => Some(BufferedRenderer::new()),
+      },
+      callbacks: Box::default(),
+      headless,
     };
+    gui.callbacks.install(gui.context.io_mut());
+
+    gui.fonts.add(&mut gui.context, FontStack::default_font())?;
+    gui.reload_fonts()?;
+    Ok(gui)
+  }
 
-    gui.reload_fonts();
-    gui
+  // receives errors that have no caller to go to, like a watched font
+  // failing to reload or the ini file failing to save on drop. they are
+  // logged as raylib warnings by default
+  pub fn set_error_hook(&mut self, hook: impl FnMut(&Error) + 'static) {
+    self.error_hook = Box::new(hook);
   }
 
   pub fn set_style(&mut self, style: Style) {
@@ -285,42 +294,282 @@ impl RaylibGui {
     }
   }
 
-  pub fn update(&mut self, rl: &mut RaylibHandle) {
-    let delta_time = rl.get_frame_time();
-    self.update_delta(rl, delta_time);
+  // scales style sizes and rebakes fonts at `scale` times their size, turns
+  // the automatic scale off. style sizes changed while the scale isn't 1
+  // are lost on the next change
+  pub fn set_ui_scale(&mut self, scale: f32) {
+    self.auto_ui_scale = false;
+    self.apply_ui_scale(scale);
+    let [framebuffer_scale, _] = self.context.io().display_framebuffer_scale;
+    self.rebake_fonts(framebuffer_scale);
+  }
+
+  // follows the dpi of the monitor the window is on
+  pub fn set_auto_ui_scale(&mut self, auto: bool) {
+    self.auto_ui_scale = auto;
+  }
+
+  pub fn ui_scale(&self) -> f32 {
+    self.ui_scale
+  }
+
+  fn apply_ui_scale(&mut self, scale: f32) {
+    if scale == self.ui_scale || scale <= 0.0 {
+      return;
+    }
+    self.ui_scale = scale;
+
+    let style = self.context.style_mut();
+    let base = *self.base_style.get_or_insert(*style);
+    let colors = style.colors;
+    *style = base;
+    style.scale_all_sizes(scale);
+    style.colors = colors;
+  }
+
+  // fonts are baked at the size they end up at in the framebuffer, then
+  // scaled back down by the framebuffer scale so their layout size matches
+  // the ui scale
+  fn rebake_fonts(&mut self, framebuffer_scale: f32) {
+    let density = self.ui_scale * framebuffer_scale;
+    if density != self.fonts.density {
+      self.fonts.density = density;
+      self.rebuild_fonts();
+      self.context.io_mut().font_global_scale = 1.0 / framebuffer_scale;
+    }
+  }
+
+  // between frames, so failures go to the error hook
+  fn rebuild_fonts(&mut self) {
+    self.fonts.rebuild(&mut self.context);
+    if let Err(error) = self.reload_fonts() {
+      (self.error_hook)(&error);
+    }
   }
 
-  pub fn update_delta(&mut self, rl: &mut RaylibHandle, delta_time: f32) {
-    self.handle_events(rl);
-    self.prepare_frame(rl, delta_time);
+  fn update_ui_scale(&mut self, input: &impl InputSource) {
+    let [dpi, _] = input.window_scale_dpi();
+    // with FLAG_WINDOW_HIGHDPI raylib already works in scaled coordinates
+    // and only the framebuffer is larger
+    let framebuffer_scale = if input.is_window_highdpi() { dpi } else { 1.0 };
+    if self.auto_ui_scale {
+      self.apply_ui_scale(dpi / framebuffer_scale);
+    }
+    self.rebake_fonts(framebuffer_scale);
   }
 
-  pub fn begin(&mut self, rl: &mut RaylibHandle) -> &mut Ui {
-    let delta_time = rl.get_frame_time();
-    self.begin_delta(rl, delta_time)
+  pub fn update(&mut self, input: &mut impl InputSource) {
+    let delta_time = input.frame_time();
+    self.update_delta(input, delta_time);
+  }
+
+  pub fn update_delta(
+    &mut self,
+    input: &mut impl InputSource,
+    delta_time: f32,
+  ) {
+    self.handle_events(input);
+    self.prepare_frame(input, delta_time);
+  }
+
+  pub fn begin(&mut self, input: &mut impl InputSource) -> &mut Ui {
+    let delta_time = input.frame_time();
+    self.begin_delta(input, delta_time)
   }
 
   pub fn begin_delta(
     &mut self,
-    rl: &mut RaylibHandle,
+    input: &mut impl InputSource,
     delta_time: f32,
   ) -> &mut Ui {
-    self.handle_events(rl);
-    self.prepare_frame(rl, delta_time);
+    self.handle_events(input);
+    self.prepare_frame(input, delta_time);
     self.new_frame()
   }
 
   pub fn end(&mut self) {
-    Renderer::render(&mut self.context);
+    let target = RenderTarget::screen(self.context.io());
+    self.render_frame(target, false);
+  }
+
+  // ends the frame like `end` and also returns a recording of it
+  pub fn end_captured(&mut self) -> FrameCapture {
+    let target = RenderTarget::screen(self.context.io());
+    let mut frame = self.render_frame(target, true).unwrap_or_default();
+    frame.font_atlas = Some(CapturedTexture::font_atlas(&mut self.context));
+    frame
+  }
+
+  fn render_frame(
+    &mut self,
+    target: RenderTarget,
+    capture: bool,
+  ) -> Option<FrameCapture> {
+    let start = Instant::now();
+    let draw_data = self.context.render();
+    let frame = capture.then(|| FrameCapture::new(draw_data));
+    let materials = &self.materials;
+    if cfg!(debug_assertions) {
+      for id in self.textures.unregistered(draw_data, materials) {
+        (self.error_hook)(&Error::UnregisteredTexture { id });
+      }
+    }
+    let stats = &mut self.stats.current;
+    stats.count_draw_data(draw_data);
+    match &mut self.buffered {
+      _ if self.headless => {},
+      Some(renderer) => {
+        renderer.render_draw_data(draw_data, target, materials, stats)
+      },
+      None => Renderer::render_draw_data(draw_data, target, materials, stats),
+    }
+    self.textures.end_frame();
+    self.stats.current.render += start.elapsed();
+    self.stats.end_frame();
+    frame
   }
 
-  pub fn reload_fonts(&mut self) {
+  // counters and timings of the last frame that was rendered
+  pub fn stats(&self) -> FrameStats {
+    self.stats.last()
+  }
+
+  // draws a window with `stats` and a short history of them every frame
+  pub fn show_stats_overlay(&mut self, show: bool) {
+    self.stats.overlay = show;
+  }
+
+  // raylib's input for game code, with whatever imgui captured in the
+  // current frame reported as not pressed
+  pub fn input<'a, I: InputSource>(&self, input: &'a I) -> GameInput<'a, I> {
+    GameInput::new(input, &self.context, &self.passthrough)
+  }
+
+  // lets `input` see the mouse over, or the keyboard while focusing, the
+  // window created with `name`, e.g. one showing the game's viewport
+  pub fn set_passthrough(&mut self, name: &str, passthrough: Passthrough) {
+    match self.passthrough.iter_mut().find(|(n, _)| n == name) {
+      Some(entry) => entry.1 = passthrough,
+      None => self.passthrough.push((name.to_owned(), passthrough)),
+    }
+  }
+
+  pub fn remove_passthrough(&mut self, name: &str) {
+    self.passthrough.retain(|(n, _)| n != name);
+  }
+
+  // replaces rlgl's default shader for the whole ui, the caller keeps the
+  // shader loaded while it is set
+  pub fn set_shader(&mut self, shader: &impl AsRef<ffi::Shader>) {
+    self.materials.shader = Some(*shader.as_ref());
+  }
+
+  pub fn reset_shader(&mut self) {
+    self.materials.shader = None;
+  }
+
+  pub fn set_blend(&mut self, blend: UiBlend) {
+    self.materials.blend = blend;
+  }
+
+  // rlgl can't be asked for its blend mode and shader, so rendering sets
+  // these back afterwards instead of raylib's defaults. set them when the
+  // ui is drawn inside `begin_blend_mode` or `begin_shader_mode`
+  pub fn set_outer_blend(&mut self, blend: Option<BlendMode>) {
+    self.materials.outer_blend = blend;
+  }
+
+  // the caller keeps the shader loaded while it is set
+  pub fn set_outer_shader(&mut self, shader: &impl AsRef<ffi::Shader>) {
+    self.materials.outer_shader = Some(*shader.as_ref());
+  }
+
+  pub fn reset_outer_shader(&mut self) {
+    self.materials.outer_shader = None;
+  }
+
+  pub fn set_texture_override(
+    &mut self,
+    texture: impl Into<imgui::TextureId>,
+    over: TextureOverride,
+  ) {
+    self.materials.set_override(texture.into(), over);
+  }
+
+  pub fn remove_texture_override(
+    &mut self,
+    texture: impl Into<imgui::TextureId>,
+  ) {
+    self.materials.remove_override(texture.into());
+  }
+
+  // a new texture id that draws `texture` with `over` applied, e.g. a
+  // grayscale version of an image for disabled widgets
+  pub fn texture_variant(
+    &mut self,
+    texture: impl Into<imgui::TextureId>,
+    over: TextureOverride,
+  ) -> imgui::TextureId {
+    self.materials.add_variant(texture.into(), over)
+  }
+
+  // ends the frame like `end` but draws it into `texture` instead of the
+  // screen, the texture is left unbound with raylib's usual flipped y so it
+  // can be drawn with a negative source height
+  pub fn render_to(&mut self, texture: &mut RenderTexture2D) {
+    let [width, height] = [texture.texture.width, texture.texture.height];
+    let io = self.context.io();
+    let target = RenderTarget::texture(io, width, height);
+    let [display_w, display_h] = io.display_size;
+
+    unsafe {
+      ffi::rlDrawRenderBatchActive();
+      let projection = ffi::rlGetMatrixProjection();
+      let modelview = ffi::rlGetMatrixModelview();
+
+      ffi::rlEnableFramebuffer(texture.id);
+      ffi::rlViewport(0, 0, width, height);
+      ffi::rlMatrixMode(ffi::RL_PROJECTION as i32);
+      ffi::rlLoadIdentity();
+      ffi::rlOrtho(0.0, display_w as f64, display_h as f64, 0.0, 0.0, 1.0);
+      ffi::rlMatrixMode(ffi::RL_MODELVIEW as i32);
+      ffi::rlLoadIdentity();
+
+      self.render_frame(target, false);
+
+      ffi::rlDrawRenderBatchActive();
+      ffi::rlDisableFramebuffer();
+      ffi::rlViewport(
+        0,
+        0,
+        ffi::rlGetFramebufferWidth(),
+        ffi::rlGetFramebufferHeight(),
+      );
+      ffi::rlSetMatrixProjection(projection);
+      ffi::rlSetMatrixModelview(modelview);
+    }
+  }
+
+  pub fn reload_fonts(&mut self) -> Result<()> {
+    self.fonts.blit_glyphs(&mut self.context);
     let fonts = self.context.fonts();
     let texture = fonts.build_rgba32_texture();
+    if self.headless {
+      self.fonts.atlas_size = [texture.width as i32, texture.height as i32];
+      self.textures.font_atlas = Some(fonts.tex_id);
+      return Ok(());
+    }
 
     unsafe {
       let data = texture.data.as_ptr() as *mut std::ffi::c_void;
       let [width, height] = [texture.width as i32, texture.height as i32];
+      // adding fonts can grow the atlas past the texture it was uploaded to
+      if fonts.tex_id != 0.into() && self.fonts.atlas_size != [width, height] {
+        ffi::rlUnloadTexture(fonts.tex_id.id() as _);
+        fonts.tex_id = 0.into();
+      }
+      self.fonts.atlas_size = [width, height];
+
       if fonts.tex_id == 0.into() {
         let id = ffi::rlLoadTexture(
           data,
@@ -329,6 +578,10 @@ impl RaylibGui {
           ffi::rlPixelFormat::RL_PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32,
           1,
         ) as u32;
+        if id == 0 {
+          self.textures.font_atlas = None;
+          return Err(Error::FontTexture { width, height });
+        }
         fonts.tex_id = (id as usize).into();
       } else {
         ffi::rlUpdateTexture(
@@ -342,104 +595,200 @@ impl RaylibGui {
         );
       }
     }
+    // a regrown atlas is a new gl texture, what was set up for the old one
+    // moves along with it
+    if let Some(previous) = self.textures.font_atlas {
+      if previous != fonts.tex_id {
+        self.materials.retarget(previous, fonts.tex_id);
+      }
+    }
+    self.textures.font_atlas = Some(fonts.tex_id);
+    Ok(())
+  }
+
+  pub fn add_font(&mut self, stack: FontStack) -> Result<FontId> {
+    let id = self.fonts.add(&mut self.context, stack)?;
+    self.reload_fonts()?;
+    Ok(id)
+  }
+
+  pub fn add_font_from_file(
+    &mut self,
+    path: impl AsRef<std::path::Path>,
+    size_pixels: f32,
+  ) -> Result<FontId> {
+    self.add_font(FontStack::from_file(path, size_pixels))
+  }
+
+  pub fn add_font_from_memory(
+    &mut self,
+    data: &[u8],
+    size_pixels: f32,
+  ) -> Result<FontId> {
+    self.add_font(FontStack::from_memory(data, size_pixels))
+  }
+
+  // the glyphs of a raylib font as an imgui font, e.g. of
+  // `get_font_default` or a bitmap font
+  pub fn add_raylib_font(
+    &mut self,
+    font: &impl AsRef<ffi::Font>,
+  ) -> Result<FontId> {
+    self.add_font(FontStack::from_raylib(font))
+  }
+
+  // merges `icons` into a font that was already added, e.g. into
+  // `default_font`, and rebuilds the atlas
+  pub fn merge_icons(&mut self, font: FontId, icons: &IconFont) -> Result<()> {
+    self.fonts.merge_icons(&mut self.context, font, icons)?;
+    self.reload_fonts()
+  }
+
+  // the font `RaylibGui::new` starts out with
+  pub fn default_font(&self) -> FontId {
+    FontId::DEFAULT
+  }
+
+  // looks for changes to the files fonts were added from between frames
+  // and rebuilds the atlas with them. `FontId`s stay valid, a file that
+  // can't be loaded leaves its font as it was and goes to the error hook
+  pub fn watch_fonts(&mut self, watch: bool) {
+    self.fonts.watch = watch;
+  }
+
+  // the imgui font to push for `id`, ask again after fonts were rebuilt
+  pub fn font(&self, id: FontId) -> imgui::FontId {
+    self.fonts.get(id)
   }
 
   pub fn new_frame(&mut self) -> &mut Ui {
-    self.context.new_frame()
+    // the last frame's draw data, and the callbacks in it, are gone now
+    self.callbacks.clear();
+    let ui = self.context.new_frame();
+    if self.stats.overlay {
+      self.stats.show_overlay(ui);
+    }
+    ui
   }
 
-  pub fn prepare_frame(&mut self, rl: &mut RaylibHandle, delta_time: f32) {
+  pub fn prepare_frame(
+    &mut self,
+    input: &mut impl InputSource,
+    delta_time: f32,
+  ) {
+    let start = Instant::now();
+    if self.fonts.poll(delta_time) {
+      self.rebuild_fonts();
+    }
+    for error in std::mem::take(&mut self.fonts.errors) {
+      (self.error_hook)(&error);
+    }
+    if let Some(pinch) = self.touch.take_pinch() {
+      self.set_ui_scale((self.ui_scale * pinch).clamp(0.5, 4.0));
+    }
+    self.update_ui_scale(input);
     let imgui_cursor = self.context.mouse_cursor();
     let io = self.context.io_mut();
 
-    if rl.is_window_fullscreen() {
-      let monitor = window::get_current_monitor();
-      io.display_size = [
-        window::get_monitor_width(monitor) as f32,
-        window::get_monitor_height(monitor) as f32,
-      ];
-    } else {
-      io.display_size =
-        [rl.get_screen_width() as f32, rl.get_screen_height() as f32];
-    }
+    io.display_size = input.screen_size();
 
-    if rl.get_window_state().window_highdpi() {
-      let resolution_scale = rl.get_window_scale_dpi();
-      io.display_framebuffer_scale = [resolution_scale.x, resolution_scale.y];
+    if input.is_window_highdpi() {
+      io.display_framebuffer_scale = input.window_scale_dpi();
     } else {
       io.display_framebuffer_scale = [1.0, 1.0];
     }
 
     io.delta_time = delta_time;
 
-    if io.want_set_mouse_pos {
-      rl.set_mouse_position(Vector2::new(io.mouse_pos[0], io.mouse_pos[1]));
-    } else {
-      io.add_mouse_pos_event([
-        rl.get_mouse_x() as f32,
-        rl.get_mouse_y() as f32,
-      ]);
+    // raylib reports the first touch as the mouse too, only one of them
+    // drives the pointer
+    let touched = self.touch.update(input, io, delta_time, self.ui_scale);
+    if touched {
+      // the touch placed the pointer, or hid it after the finger lifted
+    } else if io.want_set_mouse_pos {
+      input.set_mouse_position(Vector2::new(io.mouse_pos[0], io.mouse_pos[1]));
+    } else if self.last_frame_focused || input.is_cursor_on_screen() {
+      // an unfocused window only gets the mouse while it is over it, so
+      // hover states don't stick after switching away
+      let mouse = input.mouse_position();
+      io.add_mouse_pos_event([mouse.x, mouse.y]);
     }
 
     let mut set_mouse_event =
       |ray_mouse: MouseButton, imgui_mouse: imgui::MouseButton| {
-        if rl.is_mouse_button_pressed(ray_mouse) {
+        if input.is_mouse_button_pressed(ray_mouse) {
           io.add_mouse_button_event(imgui_mouse, true);
-        } else if rl.is_mouse_button_released(ray_mouse) {
+        } else if input.is_mouse_button_released(ray_mouse) {
           io.add_mouse_button_event(imgui_mouse, false);
         }
       };
 
-    set_mouse_event(MouseButton::MOUSE_BUTTON_LEFT, imgui::MouseButton::Left);
-    set_mouse_event(MouseButton::MOUSE_BUTTON_RIGHT, imgui::MouseButton::Right);
-    set_mouse_event(
-      MouseButton::MOUSE_BUTTON_MIDDLE,
-      imgui::MouseButton::Middle,
-    );
-    set_mouse_event(
-      MouseButton::MOUSE_BUTTON_FORWARD,
-      imgui::MouseButton::Extra1,
-    );
-    set_mouse_event(MouseButton::MOUSE_BUTTON_BACK, imgui::MouseButton::Extra2);
+    if !touched {
+      set_mouse_event(MouseButton::MOUSE_BUTTON_LEFT, imgui::MouseButton::Left);
+      set_mouse_event(
+        MouseButton::MOUSE_BUTTON_RIGHT,
+        imgui::MouseButton::Right,
+      );
+      set_mouse_event(
+        MouseButton::MOUSE_BUTTON_MIDDLE,
+        imgui::MouseButton::Middle,
+      );
+      set_mouse_event(
+        MouseButton::MOUSE_BUTTON_FORWARD,
+        imgui::MouseButton::Extra1,
+      );
+      set_mouse_event(
+        MouseButton::MOUSE_BUTTON_BACK,
+        imgui::MouseButton::Extra2,
+      );
+    }
 
-    let mouse_wheel = rl.get_mouse_wheel_move_v();
+    let mouse_wheel = input.mouse_wheel_move();
     io.add_mouse_wheel_event([mouse_wheel.x, mouse_wheel.y]);
 
     if io.backend_flags.intersects(BackendFlags::HAS_MOUSE_CURSORS) {
       if !io.config_flags.intersects(ConfigFlags::NO_MOUSE_CURSOR_CHANGE) {
         if imgui_cursor != self.current_mouse_cursor || io.mouse_draw_cursor {
           self.current_mouse_cursor = imgui_cursor;
-          if io.mouse_draw_cursor || imgui_cursor == None {
-            rl.hide_cursor();
-          } else if let Some(cursor) = imgui_cursor {
-            rl.show_cursor();
-            rl.set_mouse_cursor(to_rl_cursor(cursor));
+          if io.mouse_draw_cursor {
+            input.set_cursor(None);
+          } else {
+            input.set_cursor(imgui_cursor.map(to_rl_cursor));
           }
         }
       }
     }
+
+    self.stats.current.fps = input.fps();
+    self.stats.current.frame_time = input.frame_time();
+    self.stats.current.prepare_frame += start.elapsed();
   }
 
-  pub fn handle_events(&mut self, rl: &mut RaylibHandle) {
-    let io = self.context.io_mut();
+  pub fn handle_events(&mut self, input: &mut impl InputSource) {
+    let start = Instant::now();
 
-    //let focused = !rl.is_window_focused();
-    //if focused != self.last_frame_focused {
-    //  io.app_focus_lost = focused; // TODO: missing `add_focus_event()` function?
-    //}
-    //self.last_frame_focused = focused;
+    let focused = input.is_window_focused();
+    if focused != self.last_frame_focused {
+      self.last_frame_focused = focused;
+      unsafe {
+        sys::ImGuiIO_AddFocusEvent(self.context.io_mut().raw_mut(), focused);
+      }
+      if !focused {
+        self.release_inputs();
+      }
+    }
 
-    // TODO: (int keyId = KEY_NULL; keyId < KeyboardKey::KEY_KP_EQUAL; keyId++)
+    let io = self.context.io_mut();
     // get the pressed keys, just walk the keys so we don
-    for (rl_key, imgui_key) in self.keys {
-      if rl.is_key_pressed(rl_key) {
+    for (code, imgui_key) in self.keys.iter() {
+      if input.is_key_pressed(code) {
         io.add_key_event(imgui_key, true);
       }
     }
 
     // look for any keys that were down last frame and see if they were down and are released
-    for (rl_key, imgui_key) in self.keys {
-      if rl.is_key_released(rl_key) {
+    for (code, imgui_key) in self.keys.iter() {
+      if input.is_key_released(code) {
         io.add_key_event(imgui_key, false);
       }
     }
@@ -455,76 +804,83 @@ impl RaylibGui {
     update_mod(
       Key::ModCtrl,
       &mut self.last_control_pressed,
-      rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL)
-        || rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL),
+      input.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL as i32)
+        || input.is_key_down(KeyboardKey::KEY_LEFT_CONTROL as i32),
     );
     update_mod(
       Key::ModShift,
       &mut self.last_shift_pressed,
-      rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT)
-        || rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT),
+      input.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT as i32)
+        || input.is_key_down(KeyboardKey::KEY_LEFT_SHIFT as i32),
     );
     update_mod(
       Key::ModAlt,
       &mut self.last_alt_pressed,
-      rl.is_key_down(KeyboardKey::KEY_RIGHT_ALT)
-        || rl.is_key_down(KeyboardKey::KEY_LEFT_ALT),
+      input.is_key_down(KeyboardKey::KEY_RIGHT_ALT as i32)
+        || input.is_key_down(KeyboardKey::KEY_LEFT_ALT as i32),
     );
     update_mod(
       Key::ModSuper,
       &mut self.last_super_pressed,
-      rl.is_key_down(KeyboardKey::KEY_RIGHT_SUPER)
-        || rl.is_key_down(KeyboardKey::KEY_LEFT_SUPER),
+      input.is_key_down(KeyboardKey::KEY_RIGHT_SUPER as i32)
+        || input.is_key_down(KeyboardKey::KEY_LEFT_SUPER as i32),
     );
 
     if io.want_capture_keyboard {
       // add the text input in order
-      while let Some(pressed) = rl.get_char_pressed() {
+      while let Some(pressed) = input.get_char_pressed() {
         io.add_input_character(pressed);
       }
     }
 
-    if io.config_flags.intersects(ConfigFlags::NAV_ENABLE_GAMEPAD)
-      && rl.is_gamepad_available(0)
-    {
-      for (btn, key) in self.gamepad_map {
-        if rl.is_gamepad_button_pressed(0, btn) {
-          io.add_key_event(key, true);
-        } else if rl.is_gamepad_button_released(0, btn) {
-          io.add_key_event(key, false);
-        }
-      }
-
-      for (axis, pos_key, neg_key) in self.gamepad_axis {
-        let dead_zone = 0.20;
-        let axis_value = rl.get_gamepad_axis_movement(0, axis);
+    let nav = io.config_flags.intersects(ConfigFlags::NAV_ENABLE_GAMEPAD);
+    self.gamepad.update(input, io, nav);
 
-        io.add_key_analog_event(
-          neg_key,
-          axis_value < -dead_zone,
-          if axis_value < -dead_zone { -axis_value } else { 0.0 },
-        );
-        io.add_key_analog_event(
-          pos_key,
-          axis_value > dead_zone,
-          if axis_value > dead_zone { axis_value } else { 0.0 },
-        );
-      }
-    }
+    self.stats.current.handle_events += start.elapsed();
   }
 
   pub fn render(&mut self) {
     self.end();
   }
+
+  // raylib may never report the releases that happen while another window
+  // has focus. the focus event already cleared imgui's keys and modifiers,
+  // this lets go of the pad keys and mouse buttons that are still held
+  fn release_inputs(&mut self) {
+    let io = self.context.io_mut();
+    self.gamepad.release(io);
+
+    self.last_control_pressed = false;
+    self.last_shift_pressed = false;
+    self.last_alt_pressed = false;
+    self.last_super_pressed = false;
+
+    let buttons = [
+      imgui::MouseButton::Left,
+      imgui::MouseButton::Right,
+      imgui::MouseButton::Middle,
+      imgui::MouseButton::Extra1,
+      imgui::MouseButton::Extra2,
+    ];
+    for (button, down) in buttons.into_iter().zip(io.mouse_down) {
+      if down {
+        io.add_mouse_button_event(button, false);
+      }
+    }
+    io.add_mouse_pos_event([-f32::MAX, -f32::MAX]);
+    self.touch.reset();
+  }
 }
 
-fn save_ini_settings(ctx: &mut Context) {
+fn save_ini_settings(ctx: &mut Context) -> Result<()> {
   if let Some(ini_path) = ctx.ini_filename() {
     let mut settings = String::new();
     ctx.save_ini_settings(&mut settings);
-    let mut file = File::create(&ini_path).unwrap();
-    file.write_all(&settings.as_bytes()).unwrap();
+    File::create(&ini_path)
+      .and_then(|mut file| file.write_all(settings.as_bytes()))
+      .map_err(|error| Error::SaveSettings { path: ini_path, error })?;
   }
+  Ok(())
 }
 
 fn load_ini_settings(ctx: &mut Context) {
@@ -553,56 +909,275 @@ pub struct Renderer(());
 
 impl Renderer {
   pub fn render(ctx: &mut Context) {
+    let target = RenderTarget::screen(ctx.io());
+    let materials = Materials::default();
+    let mut stats = FrameStats::default();
+    Renderer::render_draw_data(
+      ctx.render(),
+      target,
+      None,
+      &materials,
+      &mut stats,
+    );
+  }
+
+  // replays a recorded frame, its texture ids have to be remapped onto
+  // textures loaded in this process first
+  pub fn render_capture(frame: &FrameCapture) {
+    let target = RenderTarget::capture(frame);
+    let materials = Materials::default();
+    let mut stats = FrameStats::default();
+
     unsafe {
-      ffi::rlDrawRenderBatchActive();
-      ffi::rlDisableBackfaceCulling();
+      let state = Renderer::begin(&materials);
+      let mut batch = Batch::new(target, &materials, &mut stats);
+      for draw_list in &frame.draw_lists {
+        for cmd in &draw_list.commands {
+          match *cmd {
+            // the fields are public, so a capture may not come from
+            // `read_from`, which checks this
+            CapturedCmd::Elements { count, params }
+              if !draw_list.in_range(count, &params) => {},
+            CapturedCmd::Elements { count, params } => batch.draw(
+              count,
+              params.into(),
+              &draw_list.idx_buffer,
+              &draw_list.vtx_buffer,
+            ),
+            CapturedCmd::ResetRenderState => {
+              state.reset();
+              batch.invalidate();
+            },
+            // the closure behind a callback can't be recorded
+            CapturedCmd::Callback => {},
+          }
+        }
+      }
+      state.finish();
+    }
+  }
 
-      let [width, height] = ctx.io().display_size;
-      let [scale_w, scale_h] = ctx.io().display_framebuffer_scale;
-      let [fb_width, fb_height] = [width * scale_w, height * scale_h];
+  fn render_draw_data(
+    draw_data: &DrawData,
+    target: RenderTarget,
+    materials: &Materials,
+    stats: &mut FrameStats,
+  ) {
+    unsafe {
+      let state = Renderer::begin(materials);
+      let mut batch = Batch::new(target, materials, stats);
 
-      let draw_data = ctx.render();
       if draw_data.draw_lists_count() > 0 {
         for draw_list in draw_data.draw_lists() {
           let idx_buffer = draw_list.idx_buffer();
           let vtx_buffer = draw_list.vtx_buffer();
           for cmd in draw_list.commands() {
             match cmd {
-              DrawCmd::Elements {
-                count,
-                cmd_params:
-                  DrawCmdParams { clip_rect, texture_id, idx_offset, .. },
-              } => {
-                enable_scissor(
-                  clip_rect,
-                  [scale_w, scale_h],
-                  [fb_width, fb_height],
-                );
-                render_triangles(
-                  count, idx_offset, idx_buffer, vtx_buffer, texture_id,
-                );
-                ffi::rlDrawRenderBatchActive();
+              DrawCmd::Elements { count, cmd_params } => {
+                batch.draw(count, cmd_params, idx_buffer, vtx_buffer);
+              },
+              DrawCmd::ResetRenderState => {
+                state.reset();
+                batch.invalidate();
               },
-              DrawCmd::ResetRenderState => unimplemented!(),
               DrawCmd::RawCallback { callback, raw_cmd } => {
+                ffi::rlDrawRenderBatchActive();
+                batch.stats.batch_flushes += 1;
+                batch.stats.commands += 1;
                 let clip_rect = &(*raw_cmd).ClipRect;
-                enable_scissor(
-                  [clip_rect.x, clip_rect.y, clip_rect.z, clip_rect.w],
-                  [scale_w, scale_h],
-                  [fb_width, fb_height],
-                );
+                target.scissor([
+                  clip_rect.x,
+                  clip_rect.y,
+                  clip_rect.z,
+                  clip_rect.w,
+                ]);
                 callback(draw_list.raw(), raw_cmd);
+                state.reset();
+                batch.invalidate();
               },
             }
           }
         }
       }
 
-      ffi::rlSetTexture(0);
-      ffi::rlDisableScissorTest();
-      ffi::rlEnableBackfaceCulling();
+      state.finish();
+    }
+  }
+
+  unsafe fn begin(materials: &Materials) -> RenderState {
+    ffi::rlDrawRenderBatchActive();
+    ffi::rlDisableBackfaceCulling();
+    RenderState::capture(materials)
+  }
+}
+
+// commands keep going into rlgl's batch until the clip rect changes, which
+// has to flush it because scissoring isn't part of the batch. texture,
+// shader and blend changes are handled by rlgl itself, the latter two by
+// flushing as well
+struct Batch<'a> {
+  target: RenderTarget,
+  materials: &'a Materials,
+  stats: &'a mut FrameStats,
+  clip_rect: Option<[f32; 4]>,
+  texture: Option<imgui::TextureId>,
+  material: Option<Material>,
+}
+
+impl<'a> Batch<'a> {
+  fn new(
+    target: RenderTarget,
+    materials: &'a Materials,
+    stats: &'a mut FrameStats,
+  ) -> Batch<'a> {
+    Batch {
+      target,
+      materials,
+      stats,
+      clip_rect: None,
+      texture: None,
+      material: None,
+    }
+  }
+
+  // state set behind the batch's back, e.g. by a callback
+  fn invalidate(&mut self) {
+    self.clip_rect = None;
+    self.texture = None;
+    self.material = None;
+  }
+
+  unsafe fn draw(
+    &mut self,
+    count: usize,
+    params: DrawCmdParams,
+    idx_buffer: &[u16],
+    vtx_buffer: &[DrawVert],
+  ) {
+    let material = self.materials.resolve(params.texture_id);
+    // may flush what is queued, so it has to happen under the old scissor
+    material.apply();
+    self.stats.commands += 1;
+    if self.material.is_some_and(|previous| material.switches_from(&previous)) {
+      self.stats.batch_flushes += 1;
+    }
+    self.material = Some(material);
+
+    if self.texture != Some(material.texture) {
+      self.texture = Some(material.texture);
+      self.stats.texture_switches += 1;
+    }
+
+    if self.clip_rect != Some(params.clip_rect) {
+      ffi::rlDrawRenderBatchActive();
+      self.target.scissor(params.clip_rect);
+      self.clip_rect = Some(params.clip_rect);
+      self.stats.batch_flushes += 1;
+      self.stats.scissor_changes += 1;
+    }
+
+    if ffi::rlCheckRenderBatchLimit(count as i32) {
+      self.stats.batch_flushes += 1;
+    }
+    render_triangles(
+      count,
+      params.vtx_offset,
+      params.idx_offset,
+      idx_buffer,
+      vtx_buffer,
+      material.texture,
+    );
+  }
+}
+
+// where the draw data ends up, used to map imgui clip rects onto the
+// framebuffer's bottom-left origin
+#[derive(Clone, Copy)]
+struct RenderTarget {
+  scale: [f32; 2],
+  fb_size: [f32; 2],
+}
+
+impl RenderTarget {
+  fn screen(io: &imgui::Io) -> RenderTarget {
+    let [width, height] = io.display_size;
+    let [scale_w, scale_h] = io.display_framebuffer_scale;
+    RenderTarget {
+      scale: [scale_w, scale_h],
+      fb_size: [width * scale_w, height * scale_h],
+    }
+  }
+
+  fn capture(frame: &FrameCapture) -> RenderTarget {
+    let [width, height] = frame.display_size;
+    let [scale_w, scale_h] = frame.framebuffer_scale;
+    RenderTarget {
+      scale: [scale_w, scale_h],
+      fb_size: [width * scale_w, height * scale_h],
+    }
+  }
+
+  // the whole imgui display is stretched over the texture
+  fn texture(io: &imgui::Io, width: i32, height: i32) -> RenderTarget {
+    let [display_w, display_h] = io.display_size;
+    let [width, height] = [width as f32, height as f32];
+    RenderTarget {
+      scale: [width / display_w, height / display_h],
+      fb_size: [width, height],
+    }
+  }
+
+  unsafe fn scissor(&self, clip_rect: [f32; 4]) {
+    enable_scissor(clip_rect, self.scale, self.fb_size);
+  }
+}
+
+// the rlgl state imgui expects while drawing, captured before the first draw
+// list so that callbacks which change it can be undone with a reset command
+struct RenderState {
+  projection: ffi::Matrix,
+  modelview: ffi::Matrix,
+  // rlgl can't be asked for these, they are what the caller said is set
+  // with `RaylibGui::set_outer_blend` and `set_outer_shader`
+  blend: BlendMode,
+  shader: ffi::Shader,
+}
+
+impl RenderState {
+  unsafe fn capture(materials: &Materials) -> RenderState {
+    RenderState {
+      projection: ffi::rlGetMatrixProjection(),
+      modelview: ffi::rlGetMatrixModelview(),
+      blend: materials.outer_blend.unwrap_or(BlendMode::BLEND_ALPHA),
+      shader: materials
+        .outer_shader
+        .unwrap_or_else(|| materials::default_shader()),
     }
   }
+
+  // hands rlgl back to the caller the way it was found
+  unsafe fn finish(&self) {
+    ffi::rlDrawRenderBatchActive();
+    ffi::rlSetTexture(0);
+    ffi::rlSetShader(self.shader.id, self.shader.locs);
+    ffi::rlSetBlendMode(self.blend as i32);
+    ffi::rlDisableScissorTest();
+    ffi::rlEnableBackfaceCulling();
+  }
+
+  unsafe fn reset(&self) {
+    // flush anything the callback left in the batch using its own state
+    ffi::rlDrawRenderBatchActive();
+    ffi::rlSetMatrixProjection(self.projection);
+    ffi::rlSetMatrixModelview(self.modelview);
+    ffi::rlSetTexture(0);
+    ffi::rlSetShader(self.shader.id, self.shader.locs);
+    ffi::rlDisableScissorTest();
+    ffi::rlEnableColorBlend();
+    ffi::rlSetBlendMode(self.blend as i32);
+    ffi::rlDisableDepthTest();
+    ffi::rlDisableBackfaceCulling();
+  }
 }
 
 unsafe fn enable_scissor(
@@ -624,6 +1199,7 @@ unsafe fn enable_scissor(
 
 fn render_triangles(
   count: usize,
+  vtx_offset: usize,
   idx_offset: usize,
   idx_buffer: &[u16],
   vtx_buffer: &[DrawVert],
@@ -642,9 +1218,9 @@ fn render_triangles(
       let index_b = idx_buffer[idx_offset + i + 1];
       let index_c = idx_buffer[idx_offset + i + 2];
 
-      let vertex_a = vtx_buffer[index_a as usize];
-      let vertex_b = vtx_buffer[index_b as usize];
-      let vertex_c = vtx_buffer[index_c as usize];
+      let vertex_a = vtx_buffer[vertex_index(vtx_offset, index_a)];
+      let vertex_b = vtx_buffer[vertex_index(vtx_offset, index_b)];
+      let vertex_c = vtx_buffer[vertex_index(vtx_offset, index_c)];
 
       triangle_vert(vertex_a);
       triangle_vert(vertex_b);
@@ -654,6 +1230,12 @@ fn render_triangles(
   }
 }
 
+// indices are 16 bit and count from the command's vertex offset, which is
+// how imgui splits draw lists past 65535 vertices
+pub(crate) fn vertex_index(vtx_offset: usize, index: imgui::DrawIdx) -> usize {
+  vtx_offset + index as usize
+}
+
 unsafe fn triangle_vert(idx_vert: DrawVert) {
   let [r, g, b, a] = idx_vert.col;
   let [uv_x, uv_y] = idx_vert.uv;
@@ -662,3 +1244,138 @@ unsafe fn triangle_vert(idx_vert: DrawVert) {
   ffi::rlTexCoord2f(uv_x, uv_y);
   ffi::rlVertex2f(x, y);
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn frame(keys_down: &[KeyboardKey]) -> InputFrame {
+    InputFrame {
+      keys_down: keys_down.iter().map(|&key| key as i32).collect(),
+      ..InputFrame::default()
+    }
+  }
+
+  // runs one frame of `input`'s script, `f` sees imgui after it took the
+  // frame's events in
+  fn step(
+    gui: &mut RaylibGui,
+    input: &mut ScriptedInput,
+    frame: InputFrame,
+    f: impl FnOnce(&Ui),
+  ) {
+    input.push(frame);
+    input.advance();
+    f(gui.begin(input));
+    gui.end();
+  }
+
+  #[test]
+  fn maps_keys_and_modifiers() {
+    let _lock = testing::lock();
+    let mut input = ScriptedInput::default();
+    let mut gui = RaylibGui::headless(&input);
+    gui.keys.set_code(400, Key::F1);
+    gui.keys.remove(KeyboardKey::KEY_B);
+
+    let mut held = frame(&[
+      KeyboardKey::KEY_A,
+      KeyboardKey::KEY_B,
+      KeyboardKey::KEY_LEFT_CONTROL,
+      KeyboardKey::KEY_RIGHT_SHIFT,
+    ]);
+    held.keys_down.push(400);
+    step(&mut gui, &mut input, held, |ui| {
+      assert!(ui.is_key_down(Key::A));
+      assert!(!ui.is_key_down(Key::B));
+      assert!(ui.is_key_down(Key::F1));
+      assert!(ui.is_key_down(Key::LeftCtrl));
+      assert!(ui.io().key_ctrl);
+      assert!(ui.io().key_shift);
+      assert!(!ui.io().key_alt);
+    });
+
+    step(&mut gui, &mut input, frame(&[KeyboardKey::KEY_A]), |ui| {
+      assert!(ui.is_key_down(Key::A));
+      assert!(!ui.is_key_down(Key::F1));
+      assert!(!ui.io().key_ctrl);
+      assert!(!ui.io().key_shift);
+    });
+
+    step(&mut gui, &mut input, frame(&[]), |ui| {
+      assert!(!ui.is_key_down(Key::A));
+    });
+  }
+
+  #[test]
+  fn releases_inputs_when_focus_is_lost() {
+    let _lock = testing::lock();
+    let mut input = ScriptedInput::default();
+    let mut gui = RaylibGui::headless(&input);
+    // keys and the mouse changing in one frame would be spread over two
+    gui.context.io_mut().config_input_trickle_event_queue = false;
+
+    let held = InputFrame {
+      mouse_position: Some(Vector2::new(10.0, 10.0)),
+      mouse_down: vec![MouseButton::MOUSE_BUTTON_LEFT],
+      ..frame(&[KeyboardKey::KEY_A])
+    };
+    step(&mut gui, &mut input, held.clone(), |ui| {
+      assert!(ui.is_key_down(Key::A));
+      assert!(ui.is_mouse_down(imgui::MouseButton::Left));
+    });
+
+    // raylib never reports the releases that happen in another window
+    let unfocused = InputFrame { focused: false, ..held };
+    step(&mut gui, &mut input, unfocused.clone(), |ui| {
+      assert!(!ui.is_key_down(Key::A));
+      assert!(!ui.is_mouse_down(imgui::MouseButton::Left));
+    });
+    step(&mut gui, &mut input, unfocused, |ui| {
+      assert!(!ui.is_key_down(Key::A));
+      assert!(!ui.is_mouse_down(imgui::MouseButton::Left));
+    });
+
+    // a key held through the focus change counts once it is pressed again
+    step(&mut gui, &mut input, frame(&[KeyboardKey::KEY_A]), |ui| {
+      assert!(!ui.is_key_down(Key::A));
+    });
+    step(&mut gui, &mut input, frame(&[]), |_| {});
+    step(&mut gui, &mut input, frame(&[KeyboardKey::KEY_A]), |ui| {
+      assert!(ui.is_key_down(Key::A));
+    });
+  }
+
+  #[test]
+  fn follows_the_mouse_and_sets_the_cursor() {
+    let _lock = testing::lock();
+    let mut input = ScriptedInput::default();
+    let mut gui = RaylibGui::headless(&input);
+    let at = |x, y| InputFrame {
+      mouse_position: Some(Vector2::new(x, y)),
+      ..InputFrame::default()
+    };
+
+    step(&mut gui, &mut input, at(30.0, 40.0), |ui| {
+      assert_eq!(ui.io().mouse_pos, [30.0, 40.0]);
+      ui.set_mouse_cursor(Some(imgui::MouseCursor::Hand));
+    });
+    assert_eq!(input.cursor(), Some(MouseCursor::MOUSE_CURSOR_ARROW));
+
+    step(&mut gui, &mut input, at(50.0, 60.0), |ui| {
+      assert_eq!(ui.io().mouse_pos, [50.0, 60.0]);
+      ui.set_mouse_cursor(None);
+    });
+    assert_eq!(input.cursor(), Some(MouseCursor::MOUSE_CURSOR_POINTING_HAND));
+
+    step(&mut gui, &mut input, at(50.0, 60.0), |_| {});
+    assert_eq!(input.cursor(), None);
+    step(&mut gui, &mut input, at(50.0, 60.0), |_| {});
+    assert_eq!(input.cursor(), Some(MouseCursor::MOUSE_CURSOR_ARROW));
+
+    // imgui draws the cursor itself
+    gui.context.io_mut().mouse_draw_cursor = true;
+    step(&mut gui, &mut input, at(50.0, 60.0), |_| {});
+    assert_eq!(input.cursor(), None);
+  }
+}
@@ -0,0 +1,202 @@
+use imgui::TextureId;
+use raylib::consts::BlendMode;
+use raylib::ffi;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum UiBlend {
+  #[default]
+  Alpha,
+  // blends imgui's straight alpha like `Alpha` but leaves premultiplied
+  // colors in the target, for render textures drawn with
+  // `BLEND_ALPHA_PREMULTIPLY` later
+  Premultiplied,
+  Additive,
+  // rlgl/OpenGL blend factors and equation, e.g. `ffi::RL_ONE as i32`
+  Custom {
+    src_factor: i32,
+    dst_factor: i32,
+    equation: i32,
+  },
+}
+
+impl UiBlend {
+  pub(crate) unsafe fn apply(self) {
+    let mode = match self {
+      UiBlend::Alpha => ffi::rlBlendMode::RL_BLEND_ALPHA,
+      UiBlend::Premultiplied => {
+        ffi::rlSetBlendFactorsSeparate(
+          ffi::RL_SRC_ALPHA as i32,
+          ffi::RL_ONE_MINUS_SRC_ALPHA as i32,
+          ffi::RL_ONE as i32,
+          ffi::RL_ONE_MINUS_SRC_ALPHA as i32,
+          ffi::RL_FUNC_ADD as i32,
+          ffi::RL_FUNC_ADD as i32,
+        );
+        ffi::rlBlendMode::RL_BLEND_CUSTOM_SEPARATE
+      },
+      UiBlend::Additive => ffi::rlBlendMode::RL_BLEND_ADDITIVE,
+      UiBlend::Custom { src_factor, dst_factor, equation } => {
+        ffi::rlSetBlendFactors(src_factor, dst_factor, equation);
+        ffi::rlBlendMode::RL_BLEND_CUSTOM
+      },
+    };
+    ffi::rlSetBlendMode(mode as i32);
+  }
+}
+
+// shader and blend mode used for every draw command of one texture instead
+// of the ones set on `RaylibGui`, unset fields fall back to those
+#[derive(Clone, Copy, Default)]
+pub struct TextureOverride {
+  shader: Option<ffi::Shader>,
+  blend: Option<UiBlend>,
+}
+
+impl TextureOverride {
+  pub fn new() -> TextureOverride {
+    TextureOverride::default()
+  }
+
+  // the caller keeps the shader loaded while the override is in use
+  pub fn shader(mut self, shader: &impl AsRef<ffi::Shader>) -> Self {
+    self.shader = Some(*shader.as_ref());
+    self
+  }
+
+  pub fn blend(mut self, blend: UiBlend) -> Self {
+    self.blend = Some(blend);
+    self
+  }
+}
+
+// what a draw command's texture id resolves to
+#[derive(Clone, Copy)]
+pub(crate) struct Material {
+  pub texture: TextureId,
+  pub shader: Option<ffi::Shader>,
+  pub blend: UiBlend,
+}
+
+impl Material {
+  // whether drawing with this right after `previous` has rlgl flush what
+  // it batched, or the buffered renderer switch programs
+  pub fn switches_from(&self, previous: &Material) -> bool {
+    let id = |material: &Material| material.shader.map(|shader| shader.id);
+    id(self) != id(previous) || self.blend != previous.blend
+  }
+
+  // for draws going through rlgl's batch, which picks the shader up when it
+  // is flushed
+  pub unsafe fn apply(&self) {
+    let shader = self.shader.unwrap_or_else(|| default_shader());
+    ffi::rlSetShader(shader.id, shader.locs);
+    self.blend.apply();
+  }
+}
+
+pub(crate) unsafe fn default_shader() -> ffi::Shader {
+  ffi::Shader {
+    id: ffi::rlGetShaderIdDefault(),
+    locs: ffi::rlGetShaderLocsDefault(),
+  }
+}
+
+#[derive(Default)]
+pub(crate) struct Materials {
+  pub shader: Option<ffi::Shader>,
+  pub blend: UiBlend,
+  // what rendering leaves set for the caller, none is raylib's default
+  pub outer_blend: Option<BlendMode>,
+  pub outer_shader: Option<ffi::Shader>,
+  overrides: HashMap<usize, TextureOverride>,
+  // ids handed out by `RaylibGui::texture_variant` and the texture they draw
+  variants: HashMap<usize, TextureId>,
+  next_variant: usize,
+}
+
+impl Materials {
+  pub fn set_override(&mut self, id: TextureId, over: TextureOverride) {
+    self.overrides.insert(id.id(), over);
+  }
+
+  pub fn remove_override(&mut self, id: TextureId) {
+    self.overrides.remove(&id.id());
+    self.variants.remove(&id.id());
+  }
+
+  // variant ids count down from the top of the id space so they never meet
+  // the gl texture names raylib hands out
+  pub fn add_variant(
+    &mut self,
+    texture: TextureId,
+    over: TextureOverride,
+  ) -> TextureId {
+    let id = usize::MAX - self.next_variant;
+    self.next_variant += 1;
+    self.variants.insert(id, texture);
+    self.overrides.insert(id, over);
+    id.into()
+  }
+
+  // moves the override and variants of a texture that was reuploaded under
+  // a new id
+  pub fn retarget(&mut self, from: TextureId, to: TextureId) {
+    if let Some(over) = self.overrides.remove(&from.id()) {
+      self.overrides.insert(to.id(), over);
+    }
+    for texture in self.variants.values_mut() {
+      if *texture == from {
+        *texture = to;
+      }
+    }
+  }
+
+  pub fn resolve(&self, id: TextureId) -> Material {
+    let over = self.overrides.get(&id.id()).copied().unwrap_or_default();
+    Material {
+      texture: self.variants.get(&id.id()).copied().unwrap_or(id),
+      shader: over.shader.or(self.shader),
+      blend: over.blend.unwrap_or(self.blend),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn keeps_font_atlas_overrides_when_it_is_reuploaded() {
+    let mut materials = Materials::default();
+    let [atlas, regrown] = [TextureId::new(5), TextureId::new(9)];
+    let sdf = TextureOverride::new().blend(UiBlend::Additive);
+    materials.set_override(atlas, sdf);
+    let variant = materials
+      .add_variant(atlas, TextureOverride::new().blend(UiBlend::Premultiplied));
+
+    materials.retarget(atlas, regrown);
+    assert_eq!(materials.resolve(regrown).blend, UiBlend::Additive);
+    assert_eq!(materials.resolve(atlas).blend, UiBlend::Alpha);
+    assert_eq!(materials.resolve(variant).texture, regrown);
+    assert_eq!(materials.resolve(variant).blend, UiBlend::Premultiplied);
+  }
+  #[test]
+  fn switches_on_shader_or_blend_changes() {
+    let shader = |id| ffi::Shader { id, locs: std::ptr::null_mut() };
+    let material = |texture, shader, blend| Material {
+      texture: TextureId::new(texture),
+      shader,
+      blend,
+    };
+    let alpha = UiBlend::Alpha;
+    let plain = material(1, None, alpha);
+    assert!(!material(2, None, alpha).switches_from(&plain));
+    assert!(material(1, None, UiBlend::Additive).switches_from(&plain));
+    assert!(material(1, Some(shader(4)), alpha).switches_from(&plain));
+
+    let custom = material(1, Some(shader(4)), alpha);
+    assert!(!material(2, Some(shader(4)), alpha).switches_from(&custom));
+    assert!(material(1, Some(shader(5)), alpha).switches_from(&custom));
+  }
+}
@@ -0,0 +1,156 @@
+use imgui::{internal::RawCast, sys, Context};
+use raylib::ffi::{self, PixelFormat};
+
+// one glyph of a raylib `Font` as rgba8 pixels
+struct Glyph {
+  codepoint: u32,
+  offset: [f32; 2],
+  advance: f32,
+  width: usize,
+  height: usize,
+  pixels: Vec<[u8; 4]>,
+}
+
+// the glyphs of a raylib `Font` copied out of it, so the font can be
+// unloaded once it was added to the atlas
+pub(crate) struct RaylibGlyphs {
+  pub size: f32,
+  glyphs: Vec<Glyph>,
+}
+
+impl RaylibGlyphs {
+  pub unsafe fn from_font(font: &ffi::Font) -> RaylibGlyphs {
+    let count = font.glyphCount.max(0) as usize;
+    let (infos, recs) = if count == 0 {
+      (&[][..], &[][..])
+    } else {
+      (
+        std::slice::from_raw_parts(font.glyphs, count),
+        std::slice::from_raw_parts(font.recs, count),
+      )
+    };
+
+    // fonts that don't keep their glyph images are cut out of the texture
+    let mut atlas: Option<ffi::Image> = None;
+    let glyphs = infos
+      .iter()
+      .zip(recs)
+      .map(|(info, rec)| {
+        let (image, owned) = if !info.image.data.is_null() {
+          (info.image, false)
+        } else {
+          let atlas = *atlas
+            .get_or_insert_with(|| ffi::LoadImageFromTexture(font.texture));
+          (ffi::ImageFromImage(atlas, *rec), true)
+        };
+        let pixels = image_pixels(&image);
+        if owned {
+          ffi::UnloadImage(image);
+        }
+
+        let advance =
+          if info.advanceX > 0 { info.advanceX as f32 } else { rec.width };
+        Glyph {
+          codepoint: info.value as u32,
+          offset: [info.offsetX as f32, info.offsetY as f32],
+          advance,
+          width: image.width.max(0) as usize,
+          height: image.height.max(0) as usize,
+          pixels,
+        }
+      })
+      .collect();
+
+    if let Some(atlas) = atlas {
+      ffi::UnloadImage(atlas);
+    }
+    RaylibGlyphs { size: font.baseSize as f32, glyphs }
+  }
+
+  // queues a custom rect for every glyph on the font added to the atlas
+  // last, they are filled in by `blit` once the atlas is built
+  pub unsafe fn register(&self, ctx: &mut Context, density: f32) -> Vec<i32> {
+    let atlas: &mut sys::ImFontAtlas = ctx.fonts().raw_mut();
+    let font = *atlas.Fonts.Data.add(atlas.Fonts.Size as usize - 1);
+    self
+      .glyphs
+      .iter()
+      .map(|glyph| {
+        let [width, height] = scaled_size(glyph, density);
+        sys::ImFontAtlas_AddCustomRectFontGlyph(
+          atlas,
+          font,
+          glyph.codepoint as sys::ImWchar,
+          width as i32,
+          height as i32,
+          glyph.advance * density,
+          sys::ImVec2 {
+            x: glyph.offset[0] * density,
+            y: glyph.offset[1] * density,
+          },
+        )
+      })
+      .collect()
+  }
+
+  // copies the glyphs into the built atlas, scaled with nearest sampling so
+  // pixel fonts stay sharp
+  pub unsafe fn blit(&self, ctx: &mut Context, rects: &[i32], density: f32) {
+    let atlas: &mut sys::ImFontAtlas = ctx.fonts().raw_mut();
+    let mut pixels = std::ptr::null_mut();
+    let [mut atlas_width, mut atlas_height, mut bpp] = [0, 0, 0];
+    sys::ImFontAtlas_GetTexDataAsRGBA32(
+      atlas,
+      &mut pixels,
+      &mut atlas_width,
+      &mut atlas_height,
+      &mut bpp,
+    );
+    let pixels = std::slice::from_raw_parts_mut(
+      pixels as *mut [u8; 4],
+      (atlas_width * atlas_height) as usize,
+    );
+
+    for (glyph, &index) in self.glyphs.iter().zip(rects) {
+      if glyph.pixels.is_empty() {
+        continue;
+      }
+      let rect = &*sys::ImFontAtlas_GetCustomRectByIndex(atlas, index);
+      let [width, height] = scaled_size(glyph, density);
+      for y in 0..height {
+        let src_y = ((y as f32 / density) as usize).min(glyph.height - 1);
+        for x in 0..width {
+          let src_x = ((x as f32 / density) as usize).min(glyph.width - 1);
+          let dst =
+            (rect.Y as usize + y) * atlas_width as usize + rect.X as usize + x;
+          pixels[dst] = glyph.pixels[src_y * glyph.width + src_x];
+        }
+      }
+    }
+  }
+}
+
+// imgui wants at least a pixel even for glyphs without an image
+fn scaled_size(glyph: &Glyph, density: f32) -> [usize; 2] {
+  [
+    ((glyph.width as f32 * density).round() as usize).max(1),
+    ((glyph.height as f32 * density).round() as usize).max(1),
+  ]
+}
+
+unsafe fn image_pixels(image: &ffi::Image) -> Vec<[u8; 4]> {
+  let count = (image.width.max(0) * image.height.max(0)) as usize;
+  if count == 0 {
+    return Vec::new();
+  }
+  // glyphs from LoadFontEx are coverage only
+  let coverage =
+    image.format == PixelFormat::PIXELFORMAT_UNCOMPRESSED_GRAYSCALE as i32;
+  let colors = ffi::LoadImageColors(*image);
+  let pixels = std::slice::from_raw_parts(colors, count)
+    .iter()
+    .map(|c| if coverage { [255, 255, 255, c.r] } else { [c.r, c.g, c.b, c.a] })
+    .collect();
+  ffi::UnloadImageColors(colors);
+  pixels
+}
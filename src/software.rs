@@ -0,0 +1,430 @@
+use imgui::{Context, DrawCmd, DrawCmdParams, DrawData, DrawVert, TextureId};
+use raylib::ffi;
+use raylib::prelude::*;
+use std::collections::HashMap;
+
+struct SoftwareTexture {
+  width: usize,
+  height: usize,
+  pixels: Vec<[u8; 4]>,
+}
+
+impl SoftwareTexture {
+  // nearest sampling with clamped coordinates, like raylib's default
+  // point filter
+  fn sample(&self, [u, v]: [f32; 2]) -> [f32; 4] {
+    if self.pixels.is_empty() {
+      return [0.0; 4];
+    }
+    let [w, h] = [self.width as isize, self.height as isize];
+    let x = ((u * w as f32).floor() as isize).clamp(0, w - 1) as usize;
+    let y = ((v * h as f32).floor() as isize).clamp(0, h - 1) as usize;
+    self.pixels[y * self.width + x].map(|c| c as f32 / 255.0)
+  }
+}
+
+// rgba8 pixels produced by `SoftwareRenderer`, rows top to bottom
+pub struct SoftwareImage {
+  pub width: usize,
+  pub height: usize,
+  pub data: Vec<u8>,
+}
+
+impl SoftwareImage {
+  pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+    let i = (y * self.width + x) * 4;
+    [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]]
+  }
+
+  pub fn to_image(&self) -> Image {
+    image_from_rgba8(self.width, self.height, &self.data)
+  }
+
+  pub fn export_png(&self, path: &str) -> bool {
+    self.to_image().export_image(path)
+  }
+}
+
+pub(crate) fn image_from_rgba8(
+  width: usize,
+  height: usize,
+  rgba: &[u8],
+) -> Image {
+  assert_eq!(rgba.len(), width * height * 4, "rgba8 data doesn't fit the size");
+  unsafe {
+    let data = ffi::MemAlloc(rgba.len() as u32) as *mut u8;
+    std::ptr::copy_nonoverlapping(rgba.as_ptr(), data, rgba.len());
+    Image::from_raw(ffi::Image {
+      data: data as *mut std::ffi::c_void,
+      width: width as i32,
+      height: height as i32,
+      mipmaps: 1,
+      format: PixelFormat::PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32,
+    })
+  }
+}
+
+// rasterizes imgui draw data on the cpu, so frames can be checked without
+// an opengl context
+#[derive(Default)]
+pub struct SoftwareRenderer {
+  textures: HashMap<usize, SoftwareTexture>,
+}
+
+impl SoftwareRenderer {
+  // builds the font atlas of `ctx` and keeps a copy of its pixels
+  pub fn new(ctx: &mut Context) -> SoftwareRenderer {
+    let mut renderer = SoftwareRenderer::default();
+    renderer.reload_fonts(ctx);
+    renderer
+  }
+
+  pub fn reload_fonts(&mut self, ctx: &mut Context) {
+    let fonts = ctx.fonts();
+    let tex_id = fonts.tex_id;
+    let texture = fonts.build_rgba32_texture();
+    self.add_texture(
+      tex_id,
+      texture.width as usize,
+      texture.height as usize,
+      texture.data,
+    );
+  }
+
+  pub fn add_texture(
+    &mut self,
+    id: TextureId,
+    width: usize,
+    height: usize,
+    rgba: &[u8],
+  ) {
+    assert_eq!(
+      rgba.len(),
+      width * height * 4,
+      "rgba8 data doesn't fit the size"
+    );
+    let pixels = rgba
+      .chunks_exact(4)
+      .map(|c| [c[0], c[1], c[2], c[3]])
+      .collect();
+    self.textures.insert(id.id(), SoftwareTexture { width, height, pixels });
+  }
+
+  pub fn add_image(&mut self, id: TextureId, image: &Image) {
+    let pixels = image
+      .get_image_data()
+      .iter()
+      .map(|c| [c.r, c.g, c.b, c.a])
+      .collect();
+    self.textures.insert(
+      id.id(),
+      SoftwareTexture {
+        width: image.width() as usize,
+        height: image.height() as usize,
+        pixels,
+      },
+    );
+  }
+
+  pub fn remove_texture(&mut self, id: TextureId) {
+    self.textures.remove(&id.id());
+  }
+
+  pub fn render(&self, draw_data: &DrawData) -> SoftwareImage {
+    let [display_x, display_y] = draw_data.display_pos;
+    let [display_w, display_h] = draw_data.display_size;
+    let [scale_w, scale_h] = draw_data.framebuffer_scale;
+    let width = (display_w * scale_w).max(0.0) as usize;
+    let height = (display_h * scale_h).max(0.0) as usize;
+
+    let mut target =
+      Target { width, height, pixels: vec![[0.0; 4]; width * height] };
+    let to_target = |[x, y]: [f32; 2]| {
+      [(x - display_x) * scale_w, (y - display_y) * scale_h]
+    };
+
+    for draw_list in draw_data.draw_lists() {
+      let idx_buffer = draw_list.idx_buffer();
+      let vtx_buffer = draw_list.vtx_buffer();
+      for cmd in draw_list.commands() {
+        // callbacks need raylib and a gpu, they are skipped here
+        if let DrawCmd::Elements {
+          count,
+          cmd_params:
+            DrawCmdParams { clip_rect, texture_id, vtx_offset, idx_offset },
+        } = cmd
+        {
+          let [x0, y0] = to_target([clip_rect[0], clip_rect[1]]);
+          let [x1, y1] = to_target([clip_rect[2], clip_rect[3]]);
+          let clip = [x0, y0, x1, y1];
+          let texture = self.textures.get(&texture_id.id());
+
+          let indices = &idx_buffer[idx_offset..idx_offset + count];
+          for tri in indices.chunks_exact(3) {
+            let vertex = |i: u16| {
+              let v: DrawVert = vtx_buffer[crate::vertex_index(vtx_offset, i)];
+              Vertex { pos: to_target(v.pos), uv: v.uv, col: v.col }
+            };
+            target.triangle(
+              [vertex(tri[0]), vertex(tri[1]), vertex(tri[2])],
+              clip,
+              texture,
+            );
+          }
+        }
+      }
+    }
+
+    SoftwareImage { width, height, data: target.into_rgba8() }
+  }
+}
+
+#[derive(Clone, Copy)]
+struct Vertex {
+  pos: [f32; 2],
+  uv: [f32; 2],
+  col: [u8; 4],
+}
+
+struct Target {
+  width: usize,
+  height: usize,
+  pixels: Vec<[f32; 4]>,
+}
+
+impl Target {
+  fn triangle(
+    &mut self,
+    [a, mut b, mut c]: [Vertex; 3],
+    clip: [f32; 4],
+    texture: Option<&SoftwareTexture>,
+  ) {
+    let mut area = edge(a.pos, b.pos, c.pos);
+    if area == 0.0 {
+      return;
+    }
+    if area < 0.0 {
+      std::mem::swap(&mut b, &mut c);
+      area = -area;
+    }
+
+    let min_x = a.pos[0].min(b.pos[0]).min(c.pos[0]).max(clip[0]).max(0.0);
+    let min_y = a.pos[1].min(b.pos[1]).min(c.pos[1]).max(clip[1]).max(0.0);
+    let max_x = a.pos[0]
+      .max(b.pos[0])
+      .max(c.pos[0])
+      .min(clip[2])
+      .min(self.width as f32);
+    let max_y = a.pos[1]
+      .max(b.pos[1])
+      .max(c.pos[1])
+      .min(clip[3])
+      .min(self.height as f32);
+    if min_x >= max_x || min_y >= max_y {
+      return;
+    }
+
+    for y in (min_y.floor() as usize)..(max_y.ceil() as usize) {
+      for x in (min_x.floor() as usize)..(max_x.ceil() as usize) {
+        let p = [x as f32 + 0.5, y as f32 + 0.5];
+        let [px, py] = p;
+        if px < clip[0] || px >= clip[2] || py < clip[1] || py >= clip[3] {
+          continue;
+        }
+
+        let w0 = edge(b.pos, c.pos, p);
+        let w1 = edge(c.pos, a.pos, p);
+        let w2 = edge(a.pos, b.pos, p);
+        if !covers(w0, b.pos, c.pos)
+          || !covers(w1, c.pos, a.pos)
+          || !covers(w2, a.pos, b.pos)
+        {
+          continue;
+        }
+
+        let [w0, w1, w2] = [w0 / area, w1 / area, w2 / area];
+        let lerp = |va: f32, vb: f32, vc: f32| va * w0 + vb * w1 + vc * w2;
+
+        let uv = [
+          lerp(a.uv[0], b.uv[0], c.uv[0]),
+          lerp(a.uv[1], b.uv[1], c.uv[1]),
+        ];
+        let mut color = [0.0; 4];
+        for (i, channel) in color.iter_mut().enumerate() {
+          let [ca, cb, cc] = [a.col[i], b.col[i], c.col[i]];
+          *channel = lerp(ca as f32, cb as f32, cc as f32) / 255.0;
+        }
+        if let Some(texture) = texture {
+          let texel = texture.sample(uv);
+          for (channel, t) in color.iter_mut().zip(texel) {
+            *channel *= t;
+          }
+        }
+
+        self.blend(x, y, color);
+      }
+    }
+  }
+
+  // source-over, the target is kept premultiplied until it is converted
+  fn blend(&mut self, x: usize, y: usize, [r, g, b, a]: [f32; 4]) {
+    let dst = &mut self.pixels[y * self.width + x];
+    let inv = 1.0 - a;
+    dst[0] = r * a + dst[0] * inv;
+    dst[1] = g * a + dst[1] * inv;
+    dst[2] = b * a + dst[2] * inv;
+    dst[3] = a + dst[3] * inv;
+  }
+
+  fn into_rgba8(self) -> Vec<u8> {
+    let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    self
+      .pixels
+      .iter()
+      .flat_map(|&[r, g, b, a]| {
+        let unmultiply = if a > 0.0 { 1.0 / a } else { 0.0 };
+        [
+          to_u8(r * unmultiply),
+          to_u8(g * unmultiply),
+          to_u8(b * unmultiply),
+          to_u8(a),
+        ]
+      })
+      .collect()
+  }
+}
+
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+  (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+// top-left fill rule, so pixels on an edge shared by two triangles are only
+// drawn once
+fn covers(w: f32, a: [f32; 2], b: [f32; 2]) -> bool {
+  let [dx, dy] = [b[0] - a[0], b[1] - a[1]];
+  w > 0.0 || (w == 0.0 && (dy < 0.0 || (dy == 0.0 && dx > 0.0)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing;
+  use imgui::{BackendFlags, ImColor32};
+
+  const CLEAR: [u8; 4] = [0, 0, 0, 0];
+  const RED: [u8; 4] = [255, 0, 0, 255];
+  const GREEN: [u8; 4] = [0, 255, 0, 255];
+  const BLUE: [u8; 4] = [0, 0, 255, 255];
+  const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+  fn im_color([r, g, b, a]: [u8; 4]) -> ImColor32 {
+    ImColor32::from_rgba(r, g, b, a)
+  }
+
+  fn color(x: usize, y: usize) -> [u8; 4] {
+    [x as u8, y as u8, ((x + y) % 256) as u8, 255]
+  }
+
+  #[test]
+  fn renders_draw_lists_past_16_bit_indices() {
+    let [width, height] = [200, 100];
+    let mut ctx = testing::context([width as f32, height as f32]);
+    ctx.io_mut().backend_flags |= BackendFlags::RENDERER_HAS_VTX_OFFSET;
+    let renderer = SoftwareRenderer::new(&mut ctx);
+
+    // one rect of four vertices per pixel, 80000 vertices in one list
+    let ui = ctx.new_frame();
+    let draw_list = ui.get_background_draw_list();
+    for y in 0..height {
+      for x in 0..width {
+        let [r, g, b, _] = color(x, y);
+        let min = [x as f32, y as f32];
+        let max = [x as f32 + 1.0, y as f32 + 1.0];
+        draw_list
+          .add_rect(min, max, ImColor32::from_rgb(r, g, b))
+          .filled(true)
+          .build();
+      }
+    }
+    drop(draw_list);
+    let draw_data = ctx.render();
+
+    let list =
+      draw_data.draw_lists().max_by_key(|l| l.vtx_buffer().len()).unwrap();
+    assert!(list.vtx_buffer().len() > u16::MAX as usize);
+    let offset = list.commands().any(|cmd| match cmd {
+      DrawCmd::Elements { cmd_params, .. } => cmd_params.vtx_offset > 0,
+      _ => false,
+    });
+    assert!(offset);
+
+    let image = renderer.render(draw_data);
+    for y in 0..height {
+      for x in 0..width {
+        assert_eq!(image.pixel(x, y), color(x, y), "at {x}, {y}");
+      }
+    }
+  }
+
+  #[test]
+  fn renders_rects_clipping_and_textures() {
+    let mut ctx = testing::context([64.0, 64.0]);
+    let mut renderer = SoftwareRenderer::new(&mut ctx);
+    let texture = TextureId::new(7);
+    renderer.add_texture(texture, 2, 2, &[RED, GREEN, BLUE, WHITE].concat());
+
+    let ui = ctx.new_frame();
+    let draw_list = ui.get_background_draw_list();
+    draw_list
+      .add_rect([4.0, 4.0], [12.0, 12.0], im_color(RED))
+      .filled(true)
+      .build();
+    draw_list.with_clip_rect([20.0, 4.0], [28.0, 8.0], || {
+      draw_list
+        .add_rect([20.0, 4.0], [36.0, 12.0], im_color(GREEN))
+        .filled(true)
+        .build();
+    });
+    draw_list.add_image(texture, [40.0, 40.0], [48.0, 48.0]).build();
+    drop(draw_list);
+    let image = renderer.render(ctx.render());
+
+    assert_eq!(image.pixel(4, 4), RED);
+    assert_eq!(image.pixel(11, 11), RED);
+    assert_eq!(image.pixel(3, 4), CLEAR);
+    assert_eq!(image.pixel(12, 11), CLEAR);
+
+    assert_eq!(image.pixel(20, 4), GREEN);
+    assert_eq!(image.pixel(27, 7), GREEN);
+    assert_eq!(image.pixel(27, 8), CLEAR);
+    assert_eq!(image.pixel(28, 7), CLEAR);
+
+    assert_eq!(image.pixel(41, 41), RED);
+    assert_eq!(image.pixel(46, 41), GREEN);
+    assert_eq!(image.pixel(41, 46), BLUE);
+    assert_eq!(image.pixel(46, 46), WHITE);
+    assert_eq!(image.pixel(48, 48), CLEAR);
+  }
+  #[test]
+  #[should_panic(expected = "doesn't fit the size")]
+  fn rejects_textures_shorter_than_their_size() {
+    let mut ctx = testing::context([8.0, 8.0]);
+    let mut renderer = SoftwareRenderer::new(&mut ctx);
+    renderer.add_texture(TextureId::new(7), 2, 2, &[RED, GREEN, BLUE].concat());
+  }
+
+  #[test]
+  fn samples_empty_textures_as_transparent() {
+    let mut ctx = testing::context([16.0, 16.0]);
+    let mut renderer = SoftwareRenderer::new(&mut ctx);
+    let texture = TextureId::new(7);
+    renderer.add_texture(texture, 0, 4, &[]);
+
+    let ui = ctx.new_frame();
+    ui.get_background_draw_list()
+      .add_image(texture, [4.0, 4.0], [12.0, 12.0])
+      .build();
+    let image = renderer.render(ctx.render());
+    assert_eq!(image.pixel(8, 8), CLEAR);
+  }
+}
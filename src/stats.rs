@@ -0,0 +1,132 @@
+use imgui::{DrawData, Ui};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const HISTORY_LEN: usize = 120;
+
+// what one frame cost the backend, see `RaylibGui::stats`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameStats {
+  pub draw_lists: usize,
+  pub commands: usize,
+  pub vertices: usize,
+  pub indices: usize,
+  // rlgl batch flushes for the immediate renderer, draw calls and program
+  // switches for the buffered one
+  pub batch_flushes: usize,
+  pub scissor_changes: usize,
+  pub texture_switches: usize,
+
+  pub handle_events: Duration,
+  pub prepare_frame: Duration,
+  pub render: Duration,
+
+  // as reported by raylib while the frame was prepared
+  pub fps: u32,
+  pub frame_time: f32,
+}
+
+impl FrameStats {
+  pub(crate) fn count_draw_data(&mut self, draw_data: &DrawData) {
+    self.draw_lists += draw_data.draw_lists_count();
+    self.vertices += draw_data.total_vtx_count as usize;
+    self.indices += draw_data.total_idx_count as usize;
+  }
+}
+
+#[derive(Default)]
+pub(crate) struct Stats {
+  pub current: FrameStats,
+  pub history: VecDeque<FrameStats>,
+  pub overlay: bool,
+}
+
+impl Stats {
+  pub fn end_frame(&mut self) {
+    if self.history.len() == HISTORY_LEN {
+      self.history.pop_front();
+    }
+    self.history.push_back(std::mem::take(&mut self.current));
+  }
+
+  pub fn last(&self) -> FrameStats {
+    self.history.back().copied().unwrap_or_default()
+  }
+
+  pub fn show_overlay(&self, ui: &Ui) {
+    let last = self.last();
+    let graph = |f: fn(&FrameStats) -> f32| {
+      self.history.iter().map(f).collect::<Vec<f32>>()
+    };
+    let ms = |d: Duration| d.as_secs_f32() * 1000.0;
+
+    ui.window("Renderer stats").always_auto_resize(true).build(|| {
+      ui.text(format!(
+        "{} fps, {:.2} ms frame",
+        last.fps,
+        last.frame_time * 1000.0
+      ));
+      ui.plot_lines("frame ms", &graph(|s| s.frame_time * 1000.0))
+        .graph_size([240.0, 40.0])
+        .build();
+
+      ui.separator();
+      ui.text(format!("{} lists, {} commands", last.draw_lists, last.commands));
+      ui.text(format!("{} vertices, {} indices", last.vertices, last.indices));
+      ui.text(format!(
+        "{} flushes, {} scissors, {} textures",
+        last.batch_flushes, last.scissor_changes, last.texture_switches
+      ));
+      ui.plot_lines("flushes", &graph(|s| s.batch_flushes as f32))
+        .graph_size([240.0, 40.0])
+        .build();
+
+      ui.separator();
+      ui.text(format!(
+        "events {:.3} ms, prepare {:.3} ms, render {:.3} ms",
+        ms(last.handle_events),
+        ms(last.prepare_frame),
+        ms(last.render)
+      ));
+      ui.plot_lines("render ms", &graph(|s| s.render.as_secs_f32() * 1000.0))
+        .graph_size([240.0, 40.0])
+        .build();
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing;
+  use imgui::ImColor32;
+
+  #[test]
+  fn counts_draw_data() {
+    let mut ctx = testing::context([64.0, 64.0]);
+    let ui = ctx.new_frame();
+    let red = ImColor32::from_rgb(255, 0, 0);
+    ui.get_background_draw_list().add_rect([0.0, 0.0], [8.0, 8.0], red).build();
+    ui.get_foreground_draw_list()
+      .add_rect([0.0, 0.0], [8.0, 8.0], red)
+      .filled(true)
+      .build();
+    let draw_data = ctx.render();
+    let vertices: usize =
+      draw_data.draw_lists().map(|list| list.vtx_buffer().len()).sum();
+    let indices: usize =
+      draw_data.draw_lists().map(|list| list.idx_buffer().len()).sum();
+
+    let mut stats = FrameStats::default();
+    stats.count_draw_data(draw_data);
+    assert_eq!(stats.draw_lists, draw_data.draw_lists_count());
+    assert_eq!(stats.vertices, vertices);
+    assert_eq!(stats.indices, indices);
+    assert!(vertices > 0 && indices > 0);
+
+    // renderers add their own counts on top of the same frame's
+    stats.count_draw_data(draw_data);
+    assert_eq!(stats.draw_lists, draw_data.draw_lists_count() * 2);
+    assert_eq!(stats.vertices, vertices * 2);
+  }
+}
@@ -0,0 +1,39 @@
+use imgui::Context;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+// imgui allows one context at a time, tests that create one take turns
+static LOCK: Mutex<()> = Mutex::new(());
+
+pub(crate) fn lock() -> MutexGuard<'static, ()> {
+  LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// a context that holds the lock until it is dropped
+pub(crate) struct TestContext {
+  context: Context,
+  _lock: MutexGuard<'static, ()>,
+}
+
+impl Deref for TestContext {
+  type Target = Context;
+
+  fn deref(&self) -> &Context {
+    &self.context
+  }
+}
+
+impl DerefMut for TestContext {
+  fn deref_mut(&mut self) -> &mut Context {
+    &mut self.context
+  }
+}
+
+pub(crate) fn context(display_size: [f32; 2]) -> TestContext {
+  let lock = lock();
+  let mut context = Context::create();
+  context.set_ini_filename(None::<PathBuf>);
+  context.io_mut().display_size = display_size;
+  TestContext { context, _lock: lock }
+}
@@ -0,0 +1,251 @@
+use crate::materials::Materials;
+use imgui::{DrawCmd, DrawData, TextureId};
+use raylib::ffi;
+use raylib::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+
+enum Entry {
+  Texture(Texture2D),
+  RenderTexture(RenderTexture2D),
+  Weak(WeakTexture2D),
+}
+
+// a registered texture, `T` is the raylib type it was registered as
+pub struct TextureHandle<T> {
+  id: TextureId,
+  _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> TextureHandle<T> {
+  fn new(id: u32) -> TextureHandle<T> {
+    TextureHandle { id: (id as usize).into(), _marker: PhantomData }
+  }
+
+  pub fn id(&self) -> TextureId {
+    self.id
+  }
+}
+
+impl<T> Clone for TextureHandle<T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<T> Copy for TextureHandle<T> {}
+
+impl<T> From<TextureHandle<T>> for TextureId {
+  fn from(handle: TextureHandle<T>) -> TextureId {
+    handle.id
+  }
+}
+
+// owns the raylib textures handed to imgui, textures removed mid-frame are
+// only dropped once the frame that may still reference them is rendered
+#[derive(Default)]
+pub struct TextureRegistry {
+  entries: HashMap<usize, Entry>,
+  retired: Vec<Entry>,
+  reported: HashSet<usize>,
+  pub(crate) font_atlas: Option<TextureId>,
+}
+
+impl TextureRegistry {
+  pub fn insert_texture(
+    &mut self,
+    texture: Texture2D,
+  ) -> TextureHandle<Texture2D> {
+    let handle = TextureHandle::new(texture.id);
+    self.insert(handle.id, Entry::Texture(texture));
+    handle
+  }
+
+  pub fn insert_render_texture(
+    &mut self,
+    texture: RenderTexture2D,
+  ) -> TextureHandle<RenderTexture2D> {
+    let handle = TextureHandle::new(texture.texture.id);
+    self.insert(handle.id, Entry::RenderTexture(texture));
+    handle
+  }
+
+  // the registry never unloads weak textures, the caller still has to keep
+  // the underlying texture loaded until it is removed
+  pub fn insert_weak(
+    &mut self,
+    texture: WeakTexture2D,
+  ) -> TextureHandle<WeakTexture2D> {
+    let handle = TextureHandle::new(texture.id);
+    self.insert(handle.id, Entry::Weak(texture));
+    handle
+  }
+
+  // registers a texture that stays owned by the caller, e.g. one passed
+  // straight to the `RaylibUiExt` image widgets. a texture the registry
+  // already owns stays owned, so `weak` has nothing for the handle then
+  pub fn insert_ref(
+    &mut self,
+    texture: &impl AsRef<ffi::Texture2D>,
+  ) -> TextureHandle<WeakTexture2D> {
+    self.insert_weak(unsafe { WeakTexture2D::from_raw(*texture.as_ref()) })
+  }
+
+  pub fn texture(
+    &self,
+    handle: TextureHandle<Texture2D>,
+  ) -> Option<&Texture2D> {
+    match self.entries.get(&handle.id.id()) {
+      Some(Entry::Texture(texture)) => Some(texture),
+      _ => None,
+    }
+  }
+
+  pub fn render_texture(
+    &mut self,
+    handle: TextureHandle<RenderTexture2D>,
+  ) -> Option<&mut RenderTexture2D> {
+    match self.entries.get_mut(&handle.id.id()) {
+      Some(Entry::RenderTexture(texture)) => Some(texture),
+      _ => None,
+    }
+  }
+
+  pub fn weak(
+    &self,
+    handle: TextureHandle<WeakTexture2D>,
+  ) -> Option<&WeakTexture2D> {
+    match self.entries.get(&handle.id.id()) {
+      Some(Entry::Weak(texture)) => Some(texture),
+      _ => None,
+    }
+  }
+
+  pub fn remove<T>(&mut self, handle: TextureHandle<T>) {
+    if let Some(entry) = self.entries.remove(&handle.id.id()) {
+      self.retired.push(entry);
+    }
+  }
+
+  pub fn contains(&self, id: TextureId) -> bool {
+    self.font_atlas == Some(id) || self.entries.contains_key(&id.id())
+  }
+
+  // entries are keyed by gl id, so one that is already there is the same
+  // texture. unloading it with the old entry would pull it out from under
+  // the new one
+  fn insert(&mut self, id: TextureId, entry: Entry) {
+    self.reported.remove(&id.id());
+    match (self.entries.get(&id.id()), &entry) {
+      (None | Some(Entry::Weak(_)), _) => {
+        self.entries.insert(id.id(), entry);
+      },
+      (Some(_), Entry::Weak(_)) => {},
+      (Some(_), _) => {
+        if let Some(previous) = self.entries.insert(id.id(), entry) {
+          std::mem::forget(previous);
+        }
+      },
+    }
+  }
+
+  pub(crate) fn end_frame(&mut self) {
+    self.retired.clear();
+  }
+
+  // ids `draw_data` draws that were never registered, each only returned
+  // the first time it shows up
+  pub(crate) fn unregistered(
+    &mut self,
+    draw_data: &DrawData,
+    materials: &Materials,
+  ) -> Vec<usize> {
+    let mut ids = Vec::new();
+    for draw_list in draw_data.draw_lists() {
+      for cmd in draw_list.commands() {
+        if let DrawCmd::Elements { cmd_params, .. } = cmd {
+          let id = materials.resolve(cmd_params.texture_id).texture;
+          if !self.contains(id) && self.reported.insert(id.id()) {
+            ids.push(id.id());
+          }
+        }
+      }
+    }
+    ids
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{testing, RaylibGui, ScriptedInput};
+  use std::cell::RefCell;
+  use std::rc::Rc;
+
+  // never uploaded, owned entries are forgotten before they could unload
+  fn raw(id: u32) -> ffi::Texture2D {
+    ffi::Texture2D { id, width: 1, height: 1, mipmaps: 1, format: 7 }
+  }
+
+  #[test]
+  fn keeps_owned_textures_registered_again_by_reference() {
+    let mut textures = TextureRegistry::default();
+    let owned = textures.insert_texture(unsafe { Texture2D::from_raw(raw(7)) });
+    let weak = unsafe { WeakTexture2D::from_raw(raw(7)) };
+    let by_ref = textures.insert_ref(&weak);
+
+    assert_eq!(by_ref.id(), owned.id());
+    assert!(textures.texture(owned).is_some());
+    assert!(textures.weak(by_ref).is_none());
+    assert!(textures.retired.is_empty());
+    std::mem::forget(textures);
+  }
+
+  #[test]
+  fn replaces_and_retires_entries() {
+    let mut textures = TextureRegistry::default();
+    let first =
+      textures.insert_weak(unsafe { WeakTexture2D::from_raw(raw(3)) });
+    let again =
+      textures.insert_weak(unsafe { WeakTexture2D::from_raw(raw(3)) });
+    assert!(textures.weak(again).is_some());
+    assert!(textures.retired.is_empty());
+
+    textures.remove(first);
+    assert!(!textures.contains(first.id()));
+    assert_eq!(textures.retired.len(), 1);
+    textures.end_frame();
+    assert!(textures.retired.is_empty());
+
+    let owned = textures.insert_texture(unsafe { Texture2D::from_raw(raw(3)) });
+    assert!(textures.contains(owned.id()));
+    assert!(textures.retired.is_empty());
+    std::mem::forget(textures);
+  }
+
+  #[test]
+  fn reports_unregistered_textures_once() {
+    let _lock = testing::lock();
+    let mut input = ScriptedInput::default();
+    let mut gui = RaylibGui::headless(&input);
+    let reported = Rc::new(RefCell::new(Vec::new()));
+    let errors = reported.clone();
+    gui
+      .set_error_hook(move |error| errors.borrow_mut().push(error.to_string()));
+
+    for _ in 0..2 {
+      input.advance();
+      let ui = gui.begin(&mut input);
+      let draw_list = ui.get_background_draw_list();
+      draw_list.add_image(TextureId::new(99), [0.0, 0.0], [8.0, 8.0]).build();
+      drop(draw_list);
+      gui.end();
+    }
+    if cfg!(debug_assertions) {
+      assert_eq!(
+        *reported.borrow(),
+        ["texture 99 is drawn by imgui but not registered"]
+      );
+    }
+  }
+}
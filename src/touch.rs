@@ -0,0 +1,412 @@
+use crate::InputSource;
+use imgui::{internal::RawWrapper, sys, Io, MouseButton};
+use raylib::prelude::*;
+
+// pixels a finger may wander and still tap or long press
+const TAP_SLOP: f32 = 10.0;
+// a pinch is applied once it changed the scale by this factor, the atlas
+// is rebuilt every time
+const PINCH_STEP: f32 = 1.1;
+
+enum Gesture {
+  Idle,
+  // one finger down that isn't a tap, drag or long press yet
+  Pending { start: Vector2, held: f32 },
+  Dragging,
+  // the long press already clicked, wait for the finger to lift
+  LongPressed,
+  // two fingers, their center and distance the frame before
+  TwoFinger { center: Vector2, spread: f32 },
+}
+
+// forwards raylib touch points to imgui as a touchscreen mouse, see
+// `RaylibGui::touch`. off by default as raylib also reports the first
+// touch as the mouse on most platforms
+pub struct TouchInput {
+  enabled: bool,
+  long_press: Option<f32>,
+  scroll_pixels: f32,
+  pinch_to_scale: bool,
+
+  gesture: Gesture,
+  touch_source: bool,
+  // where raylib left the mouse when the last finger lifted
+  lifted_at: Option<Vector2>,
+  pinch: f32,
+}
+
+impl Default for TouchInput {
+  fn default() -> TouchInput {
+    TouchInput {
+      enabled: false,
+      long_press: Some(0.5),
+      // about what imgui scrolls for one wheel step at the default font
+      scroll_pixels: 65.0,
+      pinch_to_scale: false,
+
+      gesture: Gesture::Idle,
+      touch_source: false,
+      lifted_at: None,
+      pinch: 1.0,
+    }
+  }
+}
+
+impl TouchInput {
+  pub fn set_enabled(&mut self, enabled: bool) {
+    self.enabled = enabled;
+  }
+
+  pub fn enabled(&self) -> bool {
+    self.enabled
+  }
+
+  // seconds a finger has to rest before it right-clicks, none turns long
+  // presses into plain left clicks
+  pub fn set_long_press(&mut self, seconds: Option<f32>) {
+    self.long_press = seconds;
+  }
+
+  // how far two fingers drag for one mouse wheel step, at a ui scale of 1
+  pub fn set_scroll_pixels(&mut self, pixels: f32) {
+    self.scroll_pixels = pixels;
+  }
+
+  // pinching changes `RaylibGui::ui_scale`, turning off the automatic one
+  pub fn set_pinch_to_scale(&mut self, pinch: bool) {
+    self.pinch_to_scale = pinch;
+  }
+
+  // returns whether touch drove the pointer, the mouse is left alone then.
+  // that includes the frames after a lift until the mouse moves, raylib
+  // keeps reporting the last touch as its position
+  pub(crate) fn update(
+    &mut self,
+    input: &impl InputSource,
+    io: &mut Io,
+    delta_time: f32,
+    ui_scale: f32,
+  ) -> bool {
+    let count = if self.enabled { input.touch_point_count() } else { 0 };
+    if count == 0 && matches!(self.gesture, Gesture::Idle) {
+      if self.touch_source {
+        self.touch_source = false;
+        set_mouse_source(io, sys::ImGuiMouseSource_Mouse);
+      }
+      if self.lifted_at == Some(input.mouse_position()) {
+        return true;
+      }
+      self.lifted_at = None;
+      return false;
+    }
+    if !self.touch_source {
+      self.touch_source = true;
+      set_mouse_source(io, sys::ImGuiMouseSource_TouchScreen);
+    }
+
+    let first = input.touch_position(0);
+    let gesture = std::mem::replace(&mut self.gesture, Gesture::Idle);
+    self.gesture = match gesture {
+      _ if count == 0 => {
+        lift(io, &gesture);
+        self.lifted_at = Some(input.mouse_position());
+        Gesture::Idle
+      },
+      // fingers lifting one by one shouldn't turn into a drag
+      Gesture::TwoFinger { center, spread } if count < 2 => {
+        Gesture::TwoFinger { center, spread }
+      },
+      Gesture::TwoFinger { center: last_center, spread: last_spread } => {
+        let (center, spread) = two_finger(input);
+        let step = self.scroll_pixels * ui_scale;
+        io.add_mouse_pos_event([center.x, center.y]);
+        io.add_mouse_wheel_event([
+          (center.x - last_center.x) / step,
+          (center.y - last_center.y) / step,
+        ]);
+        if self.pinch_to_scale && last_spread > 0.0 {
+          self.pinch *= spread / last_spread;
+        }
+        Gesture::TwoFinger { center, spread }
+      },
+      _ if count >= 2 => {
+        if let Gesture::Dragging = gesture {
+          io.add_mouse_button_event(MouseButton::Left, false);
+        }
+        let (center, spread) = two_finger(input);
+        Gesture::TwoFinger { center, spread }
+      },
+      Gesture::Idle => {
+        io.add_mouse_pos_event([first.x, first.y]);
+        Gesture::Pending { start: first, held: 0.0 }
+      },
+      Gesture::Pending { start, held } => {
+        let held = held + delta_time;
+        io.add_mouse_pos_event([first.x, first.y]);
+        if first.distance_to(start) > TAP_SLOP {
+          // press where the finger landed so sliders grab from there
+          io.add_mouse_pos_event([start.x, start.y]);
+          io.add_mouse_button_event(MouseButton::Left, true);
+          io.add_mouse_pos_event([first.x, first.y]);
+          Gesture::Dragging
+        } else if self.long_press.is_some_and(|time| held >= time) {
+          io.add_mouse_button_event(MouseButton::Right, true);
+          io.add_mouse_button_event(MouseButton::Right, false);
+          Gesture::LongPressed
+        } else {
+          Gesture::Pending { start, held }
+        }
+      },
+      Gesture::Dragging => {
+        io.add_mouse_pos_event([first.x, first.y]);
+        Gesture::Dragging
+      },
+      Gesture::LongPressed => Gesture::LongPressed,
+    };
+    true
+  }
+
+  // the scale factor pinched since the last call, once it is large enough
+  pub(crate) fn take_pinch(&mut self) -> Option<f32> {
+    let pinched = self.pinch >= PINCH_STEP || self.pinch <= 1.0 / PINCH_STEP;
+    let ended = !matches!(self.gesture, Gesture::TwoFinger { .. });
+    if pinched || (ended && self.pinch != 1.0) {
+      Some(std::mem::replace(&mut self.pinch, 1.0))
+    } else {
+      None
+    }
+  }
+
+  // forgets the gesture, the caller releases the mouse buttons
+  pub(crate) fn reset(&mut self) {
+    self.gesture = Gesture::Idle;
+  }
+}
+
+// a tap clicks where the finger was, a drag drops
+fn lift(io: &mut Io, gesture: &Gesture) {
+  match gesture {
+    Gesture::Pending { .. } => {
+      io.add_mouse_button_event(MouseButton::Left, true);
+      io.add_mouse_button_event(MouseButton::Left, false);
+    },
+    Gesture::Dragging => {
+      io.add_mouse_button_event(MouseButton::Left, false);
+    },
+    _ => {},
+  }
+  // nothing hovers once the finger is gone
+  io.add_mouse_pos_event([-f32::MAX, -f32::MAX]);
+}
+
+// the center between the first two touches and their distance
+fn two_finger(input: &impl InputSource) -> (Vector2, f32) {
+  let [a, b] = [input.touch_position(0), input.touch_position(1)];
+  ((a + b) * 0.5, a.distance_to(b))
+}
+
+fn set_mouse_source(io: &mut Io, source: sys::ImGuiMouseSource) {
+  unsafe { sys::ImGuiIO_AddMouseSourceEvent(io.raw_mut(), source) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{testing, InputFrame, RaylibGui, ScriptedInput};
+  use imgui::Ui;
+
+  const HIDDEN: [f32; 2] = [-f32::MAX, -f32::MAX];
+
+  // what the gui saw of the pointer in one frame
+  #[derive(Clone, Copy, Debug, PartialEq)]
+  struct Pointer {
+    pos: [f32; 2],
+    left: bool,
+    right: bool,
+    clicked: bool,
+    right_clicked: bool,
+    wheel: [f32; 2],
+  }
+
+  impl Pointer {
+    fn of(ui: &Ui) -> Pointer {
+      Pointer {
+        pos: ui.io().mouse_pos,
+        left: ui.is_mouse_down(MouseButton::Left),
+        right: ui.is_mouse_down(MouseButton::Right),
+        clicked: ui.is_mouse_clicked(MouseButton::Left),
+        right_clicked: ui.is_mouse_clicked(MouseButton::Right),
+        wheel: [ui.io().mouse_wheel_h, ui.io().mouse_wheel],
+      }
+    }
+  }
+
+  struct Touches {
+    gui: RaylibGui,
+    input: ScriptedInput,
+    frame_time: f32,
+    // raylib reports the first touch as the mouse and leaves it there
+    mouse: Option<Vector2>,
+  }
+
+  impl Touches {
+    fn new() -> Touches {
+      let input = ScriptedInput::default();
+      let mut gui = RaylibGui::headless(&input);
+      gui.touch.set_enabled(true);
+      Touches { gui, input, frame_time: 1.0 / 60.0, mouse: None }
+    }
+
+    fn step(&mut self, touches: &[[f32; 2]]) -> Pointer {
+      let touches: Vec<Vector2> =
+        touches.iter().map(|&[x, y]| Vector2::new(x, y)).collect();
+      self.mouse = touches.first().copied().or(self.mouse);
+      self.input.push(InputFrame {
+        frame_time: self.frame_time,
+        mouse_position: self.mouse,
+        touches,
+        ..InputFrame::default()
+      });
+      self.input.advance();
+      let pointer = Pointer::of(self.gui.begin(&mut self.input));
+      self.gui.end();
+      pointer
+    }
+
+    // frames without a finger until the queued events settled
+    fn settle(&mut self) -> Vec<Pointer> {
+      (0..4).map(|_| self.step(&[])).collect()
+    }
+  }
+
+  #[test]
+  fn taps_click_where_the_finger_was() {
+    let _lock = testing::lock();
+    let mut touches = Touches::new();
+    let pointer = touches.step(&[[100.0, 100.0]]);
+    assert_eq!(pointer.pos, [100.0, 100.0]);
+    assert!(!pointer.left);
+
+    let frames = touches.settle();
+    assert!(frames.iter().any(|pointer| pointer.clicked));
+    assert!(frames.iter().all(|pointer| !pointer.right));
+    assert_eq!(frames.last().unwrap().pos, HIDDEN);
+    assert!(!frames.last().unwrap().left);
+  }
+
+  #[test]
+  fn drags_from_where_the_finger_landed() {
+    let _lock = testing::lock();
+    let mut touches = Touches::new();
+    touches.step(&[[100.0, 100.0]]);
+    let small = touches.step(&[[105.0, 100.0]]);
+    assert!(!small.left);
+
+    // imgui holds a touch press back to the next frame when the pointer
+    // moved in the same one
+    let frames: Vec<Pointer> =
+      (0..3).map(|_| touches.step(&[[130.0, 100.0]])).collect();
+    let pressed = frames.iter().find(|pointer| pointer.left).unwrap();
+    assert_eq!(pressed.pos, [100.0, 100.0]);
+    assert_eq!(frames[2].pos, [130.0, 100.0]);
+    assert!(frames[2].left);
+    let dragged = touches.step(&[[150.0, 120.0]]);
+    assert_eq!(dragged.pos, [150.0, 120.0]);
+    assert!(dragged.left);
+
+    let frames = touches.settle();
+    assert!(!frames[0].left);
+    assert!(frames.iter().all(|pointer| !pointer.clicked));
+    assert_eq!(frames.last().unwrap().pos, HIDDEN);
+  }
+
+  #[test]
+  fn long_presses_right_click_once() {
+    let _lock = testing::lock();
+    let mut touches = Touches::new();
+    touches.frame_time = 0.2;
+    let frames: Vec<Pointer> =
+      (0..6).map(|_| touches.step(&[[100.0, 100.0]])).collect();
+    let right_clicks = frames.iter().filter(|p| p.right_clicked).count();
+    assert_eq!(right_clicks, 1);
+    assert!(frames.iter().all(|pointer| !pointer.left));
+    assert!(!frames.last().unwrap().right);
+
+    // lifting after a long press doesn't click as well
+    let frames = touches.settle();
+    assert!(frames.iter().all(|pointer| !pointer.left && !pointer.clicked));
+
+    touches.gui.touch.set_long_press(None);
+    (0..6).for_each(|_| assert!(!touches.step(&[[100.0, 100.0]]).right));
+    assert!(touches.settle().iter().any(|pointer| pointer.clicked));
+  }
+
+  #[test]
+  fn scrolls_with_two_fingers() {
+    let _lock = testing::lock();
+    let mut touches = Touches::new();
+    touches.gui.touch.set_scroll_pixels(50.0);
+    touches.step(&[[100.0, 100.0], [200.0, 100.0]]);
+    let mut frames = vec![touches.step(&[[100.0, 150.0], [200.0, 150.0]])];
+    frames
+      .extend((0..2).map(|_| touches.step(&[[100.0, 150.0], [200.0, 150.0]])));
+
+    assert_eq!(frames[0].pos, [150.0, 150.0]);
+    let wheel: f32 = frames.iter().map(|pointer| pointer.wheel[1]).sum();
+    assert_eq!(wheel, 1.0);
+    assert!(frames.iter().all(|pointer| pointer.wheel[0] == 0.0));
+    assert!(frames.iter().all(|pointer| !pointer.left));
+  }
+
+  #[test]
+  fn pinches_to_scale() {
+    let _lock = testing::lock();
+    let mut touches = Touches::new();
+    touches.step(&[[100.0, 100.0], [200.0, 100.0]]);
+    touches.step(&[[50.0, 100.0], [250.0, 100.0]]);
+    touches.step(&[[50.0, 100.0], [250.0, 100.0]]);
+    touches.settle();
+    assert_eq!(touches.gui.ui_scale(), 1.0);
+
+    touches.gui.touch.set_pinch_to_scale(true);
+    touches.step(&[[100.0, 100.0], [200.0, 100.0]]);
+    touches.step(&[[50.0, 100.0], [250.0, 100.0]]);
+    touches.step(&[[50.0, 100.0], [250.0, 100.0]]);
+    assert_eq!(touches.gui.ui_scale(), 2.0);
+  }
+
+  #[test]
+  fn moves_between_gestures() {
+    let _lock = testing::lock();
+    let mut touches = Touches::new();
+
+    // a second finger on a drag drops it, lifting them one by one neither
+    // drags nor clicks again
+    touches.step(&[[100.0, 100.0]]);
+    touches.step(&[[130.0, 100.0]]);
+    assert!(touches.step(&[[130.0, 100.0]]).left);
+    touches.step(&[[130.0, 100.0], [200.0, 100.0]]);
+    let mut frames = vec![touches.step(&[[130.0, 100.0], [200.0, 100.0]])];
+    frames.push(touches.step(&[[160.0, 100.0]]));
+    frames.extend(touches.settle());
+    assert!(frames.iter().all(|pointer| !pointer.left && !pointer.clicked));
+    assert_eq!(frames.last().unwrap().pos, HIDDEN);
+
+    // and once idle, a new finger starts over with a tap
+    touches.step(&[[300.0, 300.0]]);
+    assert!(touches.settle().iter().any(|pointer| pointer.clicked));
+  }
+
+  #[test]
+  fn hides_the_pointer_until_the_mouse_moves() {
+    let _lock = testing::lock();
+    let mut touches = Touches::new();
+    touches.step(&[[100.0, 100.0]]);
+    // raylib's mouse still sits where the finger was
+    let frames = touches.settle();
+    assert!(frames[2..].iter().all(|pointer| pointer.pos == HIDDEN));
+
+    touches.mouse = Some(Vector2::new(120.0, 110.0));
+    assert_eq!(touches.step(&[]).pos, [120.0, 110.0]);
+  }
+}
@@ -0,0 +1,135 @@
+use crate::callback::{self, RaylibCallbackContext};
+use imgui::{Image, TextureId, Ui};
+use raylib::ffi;
+use raylib::prelude::*;
+
+// rlImGui's image helpers, the textures still have to be registered with
+// `RaylibGui::textures` for as long as imgui may draw them
+pub trait RaylibUiExt {
+  fn image(&self, texture: &impl AsRef<ffi::Texture2D>);
+
+  fn image_size(&self, texture: &impl AsRef<ffi::Texture2D>, size: [f32; 2]);
+
+  // `source` is in texels and may have a negative width or height to flip
+  // the image, like raylib's `draw_texture_rec`
+  fn image_rect(
+    &self,
+    texture: &impl AsRef<ffi::Texture2D>,
+    size: [f32; 2],
+    source: Rectangle,
+  );
+
+  fn image_button(
+    &self,
+    id: &str,
+    texture: &impl AsRef<ffi::Texture2D>,
+    size: [f32; 2],
+  ) -> bool;
+
+  fn image_render_texture(&self, texture: &RenderTexture2D);
+
+  // scales the render texture to the largest size that fits the remaining
+  // content region while keeping its aspect ratio
+  fn image_render_texture_fit(&self, texture: &RenderTexture2D, center: bool);
+
+  // records `func` into the current window's draw list, it runs when the
+  // frame is rendered and the renderer's state is restored afterwards. the
+  // context has to be a `RaylibGui`'s, which drops `func` with the frame
+  fn raylib_callback<F>(&self, func: F)
+  where
+    F: FnOnce(&mut RaylibCallbackContext) + 'static;
+}
+
+impl RaylibUiExt for Ui {
+  fn image(&self, texture: &impl AsRef<ffi::Texture2D>) {
+    let texture = texture.as_ref();
+    Image::new(texture_id(texture), texture_size(texture)).build(self);
+  }
+
+  fn image_size(&self, texture: &impl AsRef<ffi::Texture2D>, size: [f32; 2]) {
+    Image::new(texture_id(texture.as_ref()), size).build(self);
+  }
+
+  fn image_rect(
+    &self,
+    texture: &impl AsRef<ffi::Texture2D>,
+    size: [f32; 2],
+    source: Rectangle,
+  ) {
+    let texture = texture.as_ref();
+    let (uv0, uv1) = source_uvs(texture, source);
+    Image::new(texture_id(texture), size).uv0(uv0).uv1(uv1).build(self);
+  }
+
+  fn image_button(
+    &self,
+    id: &str,
+    texture: &impl AsRef<ffi::Texture2D>,
+    size: [f32; 2],
+  ) -> bool {
+    self
+      .image_button_config(id, texture_id(texture.as_ref()), size)
+      .build()
+  }
+
+  fn image_render_texture(&self, texture: &RenderTexture2D) {
+    let texture = &texture.texture;
+    Image::new(texture_id(texture), texture_size(texture))
+      .uv0([0.0, 1.0])
+      .uv1([1.0, 0.0])
+      .build(self);
+  }
+
+  fn image_render_texture_fit(&self, texture: &RenderTexture2D, center: bool) {
+    let texture = &texture.texture;
+    let [width, height] = texture_size(texture);
+    let [avail_w, avail_h] = self.content_region_avail();
+    if width <= 0.0 || height <= 0.0 || avail_w <= 0.0 || avail_h <= 0.0 {
+      return;
+    }
+
+    let scale = (avail_w / width).min(avail_h / height);
+    let size = [width * scale, height * scale];
+
+    if center {
+      let [x, y] = self.cursor_pos();
+      self.set_cursor_pos([
+        x + (avail_w - size[0]) * 0.5,
+        y + (avail_h - size[1]) * 0.5,
+      ]);
+    }
+
+    Image::new(texture_id(texture), size)
+      .uv0([0.0, 1.0])
+      .uv1([1.0, 0.0])
+      .build(self);
+  }
+
+  fn raylib_callback<F>(&self, func: F)
+  where
+    F: FnOnce(&mut RaylibCallbackContext) + 'static,
+  {
+    callback::add_callback(self, func);
+  }
+}
+
+fn texture_id(texture: &ffi::Texture2D) -> TextureId {
+  (texture.id as usize).into()
+}
+
+fn texture_size(texture: &ffi::Texture2D) -> [f32; 2] {
+  [texture.width as f32, texture.height as f32]
+}
+
+fn source_uvs(
+  texture: &ffi::Texture2D,
+  source: Rectangle,
+) -> ([f32; 2], [f32; 2]) {
+  let [width, height] = texture_size(texture);
+  let (x0, x1) = (source.x / width, (source.x + source.width.abs()) / width);
+  let (y0, y1) = (source.y / height, (source.y + source.height.abs()) / height);
+
+  let (u0, u1) = if source.width < 0.0 { (x1, x0) } else { (x0, x1) };
+  let (v0, v1) = if source.height < 0.0 { (y1, y0) } else { (y0, y1) };
+  ([u0, v0], [u1, v1])
+}